@@ -7,6 +7,7 @@
    <property name="damage" type="float" value="1"/>
    <property name="fireDelay" type="int" value="1250"/>
    <property name="health" type="float" value="1"/>
+   <property name="laneSize" type="int" value="8"/>
    <property name="speed" type="int" value="40"/>
    <property name="zoom" type="float" value="3.5"/>
   </properties>
@@ -45,6 +46,9 @@
    <property name="damage" type="float" value="1"/>
    <property name="fireDelay" type="int" value="850"/>
    <property name="health" type="float" value="2"/>
+   <property name="magazine" type="int" value="5"/>
+   <property name="reloadTime" type="int" value="1500"/>
+   <property name="reserve" type="int" value="30"/>
    <property name="speed" type="int" value="40"/>
    <property name="zoom" type="float" value="3"/>
   </properties>
@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+
+use avian2d::prelude::{AngularDamping, LinearDamping};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::TiledMapAsset;
+use bevy_ecs_tiled::prelude::tiled::Properties;
+use bevy_spritesheet_animation::prelude::Animation;
+
+use crate::{
+    PHYSICS_SPEED,
+    game::{
+        actors::{
+            health::Health,
+            movement::{LookDir, MovementBundle},
+            player::CollisionDataBundle,
+            wreck::LeavesWreck,
+        },
+        state::InGame,
+        weapons::{
+            ammo::Ammo,
+            charge::{ChargeKind, SecondaryWeapon},
+            weapon::Weapon,
+        },
+    },
+    utils::tiled::{
+        destructor::{Blast, BlastShape},
+        tileset_reader::{
+            find_tileset, read_f32_property, read_sprite_animation_from_tileset,
+            read_string_property,
+        },
+    },
+};
+
+/// Name of the tileset that describes every tank type.
+pub const TANK_TILESET: &str = "tank";
+/// Archetype used by the player until the spawn point says otherwise.
+pub const DEFAULT_PLAYER_ARCHETYPE: &str = "simple";
+
+const IDLE_SUFFIX: &str = "_idle";
+const WRECK_SUFFIX: &str = "_wreck";
+
+/// Fuse of timed charges without a `chargeFuse` property, in seconds.
+const DEFAULT_CHARGE_FUSE: f32 = 3.0;
+
+/// Divider turning the `cameraSpeed` property into a camera decay rate.
+const CAMERA_SPEED_DIVIDER: f32 = 36.0;
+
+pub struct TankArchetypePlugin;
+
+impl Plugin for TankArchetypePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TankArchetypeRegistry>()
+            .register_type::<TankArchetype>()
+            .add_systems(PreUpdate, load_tank_archetypes);
+    }
+}
+
+/// Tank parameters read from the custom properties of a `tank.tsx` tile.
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct TankArchetype {
+    pub name: String,
+    pub tile_id: u32,
+    pub health: f32,
+    /// Speed in map units, not yet scaled by the physics speed.
+    pub speed: f32,
+    pub damage: f32,
+    /// Delay between shots, in seconds.
+    pub fire_delay: f32,
+    pub bullet_speed: f32,
+    /// Bullet range in map units, not yet scaled by the physics speed.
+    pub range: f32,
+    /// Width of the firing cone, in degrees.
+    pub spread: f32,
+    /// Bullets per shot.
+    pub projectiles: u32,
+    /// Bounces off armored tiles per bullet.
+    pub ricochets: u32,
+    /// Explosion radius of the bullets in tiles, 0 for plain bullets.
+    pub blast_radius: u32,
+    pub blast_shape: BlastShape,
+    /// Tile of `bullet.tsx` used for the bullets.
+    pub bullet_tile: u32,
+    /// Armor-piercing level of the bullets.
+    pub power: u32,
+    /// Rounds per magazine, 0 for a tank that never reloads.
+    pub magazine: u32,
+    /// Spare rounds, `None` for an endless supply.
+    pub reserve: Option<u32>,
+    /// Time to change the magazine, in seconds.
+    pub reload_time: f32,
+    /// Charges dropped by the secondary weapon, `None` for tanks without one.
+    pub secondary: Option<ChargeKind>,
+    /// Explosion radius of the charges in tiles.
+    pub charge_radius: u32,
+    pub charge_damage: f32,
+    /// Distance between the lanes the tank is steered onto after a turn, in map units.
+    /// 0 turns the assistance off, see [`LaneAlignment`](super::movement::LaneAlignment).
+    pub lane_size: f32,
+    pub zoom: f32,
+    pub camera_speed: f32,
+    /// Tile shown once the tank is destroyed, from the `<name>_wreck` tile.
+    pub wreck_tile_id: Option<u32>,
+}
+
+impl Default for TankArchetype {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_PLAYER_ARCHETYPE.to_string(),
+            tile_id: 0,
+            health: 1.0,
+            speed: 40.0,
+            damage: 1.0,
+            fire_delay: 1.25,
+            bullet_speed: 800.0,
+            range: 1000.0,
+            spread: 0.0,
+            projectiles: 1,
+            ricochets: 0,
+            blast_radius: 0,
+            blast_shape: BlastShape::Circle,
+            bullet_tile: 0,
+            power: 0,
+            magazine: 0,
+            reserve: None,
+            reload_time: 2.0,
+            secondary: None,
+            charge_radius: 1,
+            charge_damage: 2.0,
+            lane_size: 0.0,
+            zoom: 3.5,
+            camera_speed: 40.0,
+            wreck_tile_id: None,
+        }
+    }
+}
+
+impl TankArchetype {
+    pub fn from_properties(name: &str, tile_id: u32, properties: &Properties) -> Self {
+        let default = Self::default();
+
+        Self {
+            name: name.to_string(),
+            tile_id,
+            health: read_f32_property(properties, "health").unwrap_or(default.health),
+            speed: read_f32_property(properties, "speed").unwrap_or(default.speed),
+            damage: read_f32_property(properties, "damage").unwrap_or(default.damage),
+            fire_delay: read_f32_property(properties, "fireDelay")
+                .map(|ms| ms / 1000.0)
+                .unwrap_or(default.fire_delay),
+            bullet_speed: read_f32_property(properties, "bulletSpeed")
+                .unwrap_or(default.bullet_speed),
+            range: read_f32_property(properties, "range").unwrap_or(default.range),
+            spread: read_f32_property(properties, "spread").unwrap_or(default.spread),
+            projectiles: read_f32_property(properties, "projectiles")
+                .map_or(default.projectiles, |count| count as u32),
+            ricochets: read_f32_property(properties, "ricochets")
+                .map_or(default.ricochets, |count| count as u32),
+            blast_radius: read_f32_property(properties, "blastRadius")
+                .map_or(default.blast_radius, |radius| radius as u32),
+            blast_shape: match read_string_property(properties, "blastShape") {
+                Some("square") => BlastShape::Square,
+                _ => default.blast_shape,
+            },
+            bullet_tile: read_f32_property(properties, "bulletTile")
+                .map_or(default.bullet_tile, |tile| tile as u32),
+            power: read_f32_property(properties, "power")
+                .map_or(default.power, |power| power as u32),
+            magazine: read_f32_property(properties, "magazine")
+                .map_or(default.magazine, |count| count as u32),
+            // Отрицательный запас — бесконечные патроны
+            reserve: read_f32_property(properties, "reserve").map_or(default.reserve, |count| {
+                (count >= 0.0).then_some(count as u32)
+            }),
+            reload_time: read_f32_property(properties, "reloadTime")
+                .map(|ms| ms / 1000.0)
+                .unwrap_or(default.reload_time),
+            secondary: match read_string_property(properties, "secondary") {
+                Some("mine") => Some(ChargeKind::Mine),
+                Some("charge") => Some(ChargeKind::Timed {
+                    fuse: read_f32_property(properties, "chargeFuse")
+                        .map_or(DEFAULT_CHARGE_FUSE, |ms| ms / 1000.0),
+                }),
+                _ => default.secondary,
+            },
+            charge_radius: read_f32_property(properties, "chargeRadius")
+                .map_or(default.charge_radius, |radius| radius as u32),
+            charge_damage: read_f32_property(properties, "chargeDamage")
+                .unwrap_or(default.charge_damage),
+            lane_size: read_f32_property(properties, "laneSize").unwrap_or(default.lane_size),
+            zoom: read_f32_property(properties, "zoom").unwrap_or(default.zoom),
+            camera_speed: read_f32_property(properties, "cameraSpeed")
+                .unwrap_or(default.camera_speed),
+            wreck_tile_id: None,
+        }
+    }
+
+    pub fn movement_speed(&self) -> f32 {
+        self.speed / PHYSICS_SPEED
+    }
+
+    pub fn weapon(&self) -> Weapon {
+        Weapon {
+            fire_rate: 1.0 / self.fire_delay.max(f32::EPSILON),
+            projectile_speed: self.bullet_speed,
+            range: self.range / PHYSICS_SPEED,
+            spread: self.spread,
+            projectile_count: self.projectiles.max(1),
+            damage: self.damage,
+            ricochets: self.ricochets,
+            blast: (self.blast_radius > 0).then_some(Blast {
+                radius: self.blast_radius,
+                shape: self.blast_shape,
+            }),
+            bullet_tile: self.bullet_tile,
+            power: self.power,
+            ..default()
+        }
+    }
+
+    /// Magazine for archetypes that have to reload.
+    pub fn ammo(&self) -> Option<Ammo> {
+        (self.magazine > 0).then(|| Ammo::new(self.magazine, self.reserve, self.reload_time))
+    }
+
+    pub fn secondary_weapon(&self) -> Option<SecondaryWeapon> {
+        self.secondary.map(|kind| SecondaryWeapon {
+            damage: self.charge_damage,
+            power: self.power,
+            ..SecondaryWeapon::new(
+                kind,
+                Blast {
+                    radius: self.charge_radius,
+                    shape: self.blast_shape,
+                },
+            )
+        })
+    }
+
+    /// Orthographic projection scale matching the `zoom` property.
+    pub fn camera_scale(&self) -> f32 {
+        1.0 / self.zoom.max(f32::EPSILON)
+    }
+
+    pub fn camera_decay_rate(&self) -> f32 {
+        self.camera_speed / CAMERA_SPEED_DIVIDER
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct TankArchetypeRegistry {
+    archetypes: HashMap<String, TankArchetype>,
+}
+
+impl TankArchetypeRegistry {
+    pub fn insert(&mut self, archetype: TankArchetype) -> &mut TankArchetypeRegistry {
+        self.archetypes.insert(archetype.name.clone(), archetype);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TankArchetype> {
+        self.archetypes.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.archetypes.is_empty()
+    }
+}
+
+fn load_tank_archetypes(
+    mut registry: ResMut<TankArchetypeRegistry>,
+    tiled_map_assets: Res<Assets<TiledMapAsset>>,
+) {
+    if !registry.is_empty() {
+        return;
+    }
+
+    let Some(tileset) = find_tileset(TANK_TILESET, &tiled_map_assets) else {
+        return;
+    };
+
+    let mut wrecks = HashMap::new();
+
+    for (tile_id, tile) in tileset.tiles() {
+        let Some(name) = &tile.user_type else {
+            continue;
+        };
+
+        if let Some(archetype_name) = name.strip_suffix(WRECK_SUFFIX) {
+            wrecks.insert(archetype_name.to_string(), tile_id);
+            continue;
+        }
+
+        if name.ends_with(IDLE_SUFFIX) {
+            continue;
+        }
+
+        registry.insert(TankArchetype::from_properties(
+            name,
+            tile_id,
+            &tile.properties,
+        ));
+    }
+
+    for (name, wreck_tile_id) in wrecks {
+        if let Some(archetype) = registry.archetypes.get_mut(&name) {
+            archetype.wreck_tile_id = Some(wreck_tile_id);
+        }
+    }
+}
+
+/// Spawns tank actors by archetype name, taking sprite and stats from the tileset.
+#[derive(SystemParam)]
+pub struct TankSpawner<'w> {
+    registry: Res<'w, TankArchetypeRegistry>,
+    tiled_map_assets: Res<'w, Assets<TiledMapAsset>>,
+    animations: ResMut<'w, Assets<Animation>>,
+    atlas_layouts: ResMut<'w, Assets<TextureAtlasLayout>>,
+    images: ResMut<'w, Assets<Image>>,
+}
+
+impl TankSpawner<'_> {
+    pub fn is_ready(&self) -> bool {
+        !self.registry.is_empty()
+    }
+
+    pub fn archetype(&self, name: &str) -> Option<&TankArchetype> {
+        self.registry.get(name)
+    }
+
+    pub fn spawn(&mut self, commands: &mut Commands, name: &str, position: Vec3) -> Option<Entity> {
+        let archetype = self.registry.get(name)?.clone();
+
+        let (sprite, animation) = read_sprite_animation_from_tileset(
+            TANK_TILESET.to_string(),
+            archetype.tile_id,
+            &self.tiled_map_assets,
+            &mut self.animations,
+            &mut self.atlas_layouts,
+            &self.images,
+        )?;
+
+        let wreck_tile_id = archetype.wreck_tile_id;
+        let ammo = archetype.ammo();
+        let secondary = archetype.secondary_weapon();
+
+        let mut entity_commands = commands.spawn((
+            Transform::from_translation(position),
+            CollisionDataBundle::new(),
+            MovementBundle::new(LookDir::Up),
+            sprite,
+            animation,
+            LinearDamping(10.0),
+            AngularDamping(0.0),
+            Health::new(archetype.health),
+            archetype.weapon(),
+            archetype,
+            DespawnOnExit(InGame),
+        ));
+
+        if let Some(ammo) = ammo {
+            entity_commands.insert(ammo);
+        }
+
+        if let Some(secondary) = secondary {
+            entity_commands.insert(secondary);
+        }
+
+        if let Some(tile_id) = wreck_tile_id {
+            entity_commands.insert(LeavesWreck {
+                tileset: TANK_TILESET.to_string(),
+                tile_id,
+            });
+        }
+
+        Some(entity_commands.id())
+    }
+}
@@ -0,0 +1,270 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_ecs_tiled::prelude::*;
+use rand::Rng;
+
+use crate::{
+    game::{
+        actors::{
+            archetype::TankSpawner,
+            movement::{Facing, LookDir, MoveIntent, apply_move_intent},
+            player::Player,
+            team::Team,
+        },
+        map_tiles::{
+            brick::{Brick, HeavyBrick},
+            water::Water,
+        },
+        state::{GameState, InGame, actors_active},
+        weapons::weapon::FireWeapon,
+    },
+    utils::{
+        pathfinding::{NavCell, NavGrid},
+        region_deactivation::{Deactivated, RegionAware},
+    },
+};
+
+pub const DEFAULT_ENEMY_ARCHETYPE: &str = "simple";
+
+/// Size of a navigation cell, roughly one tank.
+const NAV_CELL_SIZE: f32 = 16.0;
+const MAX_SEARCH_NODES: usize = 2000;
+
+const CHASE_DISTANCE: f32 = 160.0;
+const SHOOT_DISTANCE: f32 = 120.0;
+/// How far off the firing line the player may be and still get shot at.
+const AIM_TOLERANCE: f32 = 4.0;
+const PATROL_RADIUS: f32 = 64.0;
+const REPATH_INTERVAL: f32 = 0.5;
+const WAYPOINT_REACHED_DISTANCE: f32 = 2.0;
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(NavGrid::new(NAV_CELL_SIZE))
+            .add_systems(
+                Update,
+                rebuild_nav_grid
+                    .run_if(in_state(InGame).or(in_state(GameState::MainMenu)))
+                    .run_if(on_timer(Duration::from_secs_f32(1.0))),
+            )
+            .add_systems(
+                FixedUpdate,
+                enemy_ai_system
+                    .before(apply_move_intent)
+                    .run_if(actors_active),
+            )
+            .add_systems(OnExit(InGame), clear_nav_grid)
+            .add_systems(OnExit(GameState::MainMenu), clear_nav_grid);
+    }
+}
+
+#[derive(Component)]
+pub struct Enemy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnemyState {
+    #[default]
+    Patrol,
+    Chase,
+}
+
+#[derive(Component)]
+pub struct EnemyAi {
+    pub state: EnemyState,
+    pub home: Vec2,
+    goal: Option<Vec2>,
+    /// Remaining waypoints, the next one is the last element.
+    path: Vec<Vec2>,
+    repath_timer: Timer,
+}
+
+impl EnemyAi {
+    pub fn new(home: Vec2) -> Self {
+        Self {
+            state: EnemyState::Patrol,
+            home,
+            goal: None,
+            path: Vec::new(),
+            repath_timer: Timer::from_seconds(REPATH_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    tank_spawner: &mut TankSpawner,
+    archetype: &str,
+    position: Vec2,
+) -> Option<Entity> {
+    let id = tank_spawner.spawn(commands, archetype, position.extend(-150.0))?;
+
+    commands.entity(id).insert((
+        Enemy,
+        EnemyAi::new(position),
+        Facing(LookDir::Down),
+        Team::ENEMIES,
+        RegionAware,
+    ));
+
+    Some(id)
+}
+
+fn rebuild_nav_grid(
+    mut nav_grid: ResMut<NavGrid>,
+    tiles: Query<
+        (&TilePos, &TilemapId, Has<HeavyBrick>, Has<Water>),
+        Or<(With<Brick>, With<Water>)>,
+    >,
+    tilemaps: Query<(
+        &TilemapSize,
+        &TilemapGridSize,
+        &TilemapTileSize,
+        &TilemapType,
+        &TilemapAnchor,
+        &GlobalTransform,
+    )>,
+) {
+    nav_grid.clear();
+
+    for (tile_pos, tilemap_id, is_heavy, is_water) in tiles.iter() {
+        let Ok((size, grid, tile, map_type, anchor, transform)) = tilemaps.get(tilemap_id.0) else {
+            continue;
+        };
+
+        let local = tile_pos.center_in_world(size, grid, tile, map_type, anchor);
+        let world = transform.transform_point(local.extend(0.0)).truncate();
+        let cell = nav_grid.world_to_cell(world);
+
+        let kind = if is_heavy || is_water {
+            NavCell::Obstacle
+        } else {
+            NavCell::Destructible
+        };
+        nav_grid.mark(cell, kind);
+    }
+}
+
+fn clear_nav_grid(mut nav_grid: ResMut<NavGrid>) {
+    nav_grid.clear();
+}
+
+#[allow(clippy::type_complexity)]
+fn enemy_ai_system(
+    time: Res<Time>,
+    nav_grid: Res<NavGrid>,
+    player: Option<Single<&Transform, (With<Player>, Without<Enemy>)>>,
+    mut enemies: Query<
+        (
+            Entity,
+            &Transform,
+            &mut EnemyAi,
+            &mut Facing,
+            &mut MoveIntent,
+        ),
+        (With<Enemy>, Without<Deactivated>),
+    >,
+    mut fire: MessageWriter<FireWeapon>,
+) {
+    let player_position = player.map(|player| player.translation.truncate());
+    let mut rng = rand::rng();
+
+    for (entity, transform, mut ai, mut facing, mut intent) in enemies.iter_mut() {
+        let position = transform.translation.truncate();
+        let target = player_position.filter(|target| target.distance(position) <= CHASE_DISTANCE);
+
+        ai.state = if target.is_some() {
+            EnemyState::Chase
+        } else {
+            EnemyState::Patrol
+        };
+        ai.repath_timer.tick(time.delta());
+
+        let mut direction = None;
+        let mut wants_to_fire = false;
+
+        if let Some(target) = target
+            && let Some(aim) = aim_direction(position, target)
+        {
+            // Игрок на линии огня — разворачиваемся и стреляем
+            facing.set_if_neq(Facing(aim));
+            wants_to_fire = true;
+        } else {
+            if ai.state == EnemyState::Chase {
+                ai.goal = target;
+            } else if ai.goal.is_none() {
+                let offset = Vec2::new(
+                    rng.random_range(-PATROL_RADIUS..PATROL_RADIUS),
+                    rng.random_range(-PATROL_RADIUS..PATROL_RADIUS),
+                );
+                ai.goal = Some(ai.home + offset);
+            }
+
+            if (ai.repath_timer.just_finished() || ai.path.is_empty())
+                && let Some(goal) = ai.goal
+            {
+                ai.path = nav_grid
+                    .find_path(position, goal, MAX_SEARCH_NODES)
+                    .map(|mut path| {
+                        path.reverse();
+                        path
+                    })
+                    .unwrap_or_default();
+
+                if ai.path.is_empty() {
+                    ai.goal = None;
+                }
+            }
+
+            while ai
+                .path
+                .last()
+                .is_some_and(|next| next.distance(position) <= WAYPOINT_REACHED_DISTANCE)
+            {
+                ai.path.pop();
+            }
+
+            if let Some(&next) = ai.path.last() {
+                if let Some(dir) = LookDir::from_vec2(next - position) {
+                    facing.set_if_neq(Facing(dir));
+
+                    // Кирпич на пути — пробиваем его, а не едем в стену
+                    let ahead = nav_grid.world_to_cell(position + dir.to_vec2() * NAV_CELL_SIZE);
+                    if nav_grid.get(ahead) == Some(NavCell::Destructible) {
+                        wants_to_fire = true;
+                    } else {
+                        direction = Some(dir);
+                    }
+                }
+            } else {
+                ai.goal = None;
+            }
+        }
+
+        intent.set_if_neq(MoveIntent(direction.map_or(Vec2::ZERO, LookDir::to_vec2)));
+
+        if wants_to_fire {
+            fire.write(FireWeapon {
+                shooter: entity,
+                direction: facing.0.to_vec2(),
+            });
+        }
+    }
+}
+
+/// Direction to shoot at the target if it is on the same row or column and close enough.
+fn aim_direction(position: Vec2, target: Vec2) -> Option<LookDir> {
+    let diff = target - position;
+
+    if diff.length() > SHOOT_DISTANCE {
+        return None;
+    }
+
+    if diff.x.abs() <= AIM_TOLERANCE || diff.y.abs() <= AIM_TOLERANCE {
+        LookDir::from_vec2(diff)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<DamageEvent>()
+            .add_message::<DeathEvent>()
+            .register_type::<Health>()
+            .register_type::<Damage>()
+            .configure_sets(
+                FixedPostUpdate,
+                (HealthSet::Damage, HealthSet::Cleanup).chain(),
+            )
+            .add_systems(FixedPostUpdate, apply_damage.in_set(HealthSet::Damage))
+            .add_systems(FixedPostUpdate, despawn_dead.in_set(HealthSet::Cleanup));
+    }
+}
+
+/// Systems reading a [`DeathEvent`] go between these sets, while the dead entity is untouched.
+/// Systems replacing or despawning dead entities go into `Cleanup`.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HealthSet {
+    Damage,
+    Cleanup,
+}
+
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+/// Amount of health removed from whatever this entity hits.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+pub struct Damage(pub f32);
+
+/// Entity has already died and is waiting to be cleaned up.
+#[derive(Component)]
+pub struct Dead;
+
+/// Entity is not despawned on death, another system decides what happens to it.
+#[derive(Component, Default)]
+pub struct KeepOnDeath;
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    /// Entity that caused the damage, e.g. the tank that fired the bullet.
+    pub source: Option<Entity>,
+    pub amount: f32,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub killer: Option<Entity>,
+    pub position: Vec2,
+}
+
+fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: MessageReader<DamageEvent>,
+    mut death_events: MessageWriter<DeathEvent>,
+    mut targets: Query<(&mut Health, &GlobalTransform), Without<Dead>>,
+) {
+    for event in damage_events.read() {
+        let Ok((mut health, transform)) = targets.get_mut(event.target) else {
+            continue;
+        };
+
+        if health.is_dead() {
+            continue;
+        }
+
+        health.current = (health.current - event.amount).max(0.0);
+
+        if health.is_dead() {
+            commands.entity(event.target).insert(Dead);
+            death_events.write(DeathEvent {
+                entity: event.target,
+                killer: event.source,
+                position: transform.translation().truncate(),
+            });
+        }
+    }
+}
+
+fn despawn_dead(
+    mut commands: Commands,
+    mut death_events: MessageReader<DeathEvent>,
+    kept: Query<(), With<KeepOnDeath>>,
+) {
+    for event in death_events.read() {
+        if kept.contains(event.entity) {
+            continue;
+        }
+
+        if let Ok(mut entity_commands) = commands.get_entity(event.entity) {
+            entity_commands.despawn();
+        }
+    }
+}
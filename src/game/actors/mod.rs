@@ -1,2 +1,7 @@
+pub mod archetype;
+pub mod enemy;
+pub mod health;
 pub mod movement;
 pub mod player;
+pub mod team;
+pub mod wreck;
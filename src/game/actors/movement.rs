@@ -1,17 +1,24 @@
 use crate::PLAYER_SPEED;
+use crate::game::actors::archetype::TankArchetype;
+use crate::game::actors::health::Dead;
 use crate::game::actors::player::Player;
-use avian2d::prelude::LinearVelocity;
-use bevy::ecs::resource::Resource;
-use bevy::ecs::system::ResMut;
-use bevy::input::ButtonInput;
+use crate::game::controls::{ActionState, InputBindings};
+use crate::utils::region_deactivation::Deactivated;
+use crate::utils::tiled::destructor::{AffectedByDestructor, get_tile_pos_from_world_pos};
+use avian2d::prelude::{LinearVelocity, Position};
+use bevy::ecs::bundle::Bundle;
+use bevy::ecs::component::Component;
+use bevy::ecs::query::{Changed, Without};
 use bevy::math::{Quat, Vec2, Vec3};
-use bevy::prelude::{KeyCode, Query, Res, With};
-use bevy::transform::components::Transform;
+use bevy::prelude::{DetectChangesMut, Query, Res, With};
+use bevy::time::Time;
+use bevy::transform::components::{GlobalTransform, Transform};
+use bevy_ecs_tiled::prelude::{
+    TilePos, TilemapAnchor, TilemapGridSize, TilemapSize, TilemapTileSize, TilemapType,
+};
 
-#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
-pub struct PlayerMoving {
-    pub is_moving: bool,
-}
+/// Lane offset small enough to count as aligned.
+const LANE_TOLERANCE: f32 = 0.05;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LookDir {
@@ -34,107 +41,292 @@ impl LookDir {
         let vec2 = self.to_vec2();
         Vec3::new(vec2.x, vec2.y, 0.0)
     }
+
+    /// Picks the direction along the dominant axis of the vector.
+    pub fn from_vec2(vec: Vec2) -> Option<LookDir> {
+        if vec == Vec2::ZERO {
+            return None;
+        }
+
+        Some(if vec.x.abs() > vec.y.abs() {
+            if vec.x > 0.0 {
+                LookDir::Right
+            } else {
+                LookDir::Left
+            }
+        } else if vec.y > 0.0 {
+            LookDir::Up
+        } else {
+            LookDir::Down
+        })
+    }
+
+    pub fn to_rotation(self) -> Quat {
+        let angle = match self {
+            LookDir::Up => 0.0,
+            LookDir::Right => -std::f32::consts::FRAC_PI_2,
+            LookDir::Down => std::f32::consts::PI,
+            LookDir::Left => std::f32::consts::FRAC_PI_2,
+        };
+
+        Quat::from_rotation_z(angle)
+    }
 }
 
-#[derive(Resource, Default, Clone, Copy)]
-pub struct PlayerLookDir(pub Option<LookDir>);
+/// Direction the actor wants to drive in, zero to stand still.
+/// Written by player input, AI or network code and applied by [`apply_move_intent`].
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq)]
+pub struct MoveIntent(pub Vec2);
 
-#[derive(Resource, Default, Clone, Copy)]
-pub struct LastMoveDir(pub Option<Vec2>);
+/// Where the actor looks and shoots.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Facing(pub LookDir);
 
-pub fn move_player(
-    mut player: Query<&mut LinearVelocity, With<Player>>,
-    kb_input: Res<ButtonInput<KeyCode>>,
-    mut last_dir: ResMut<LastMoveDir>,
-    mut look_dir: ResMut<PlayerLookDir>,
-    mut moving: ResMut<PlayerMoving>,
-) {
-    // обновляем направление только по just_pressed (последняя клавиша важнее)
-    if kb_input.pressed(KeyCode::KeyW) {
-        last_dir.0 = Some(Vec2::Y);
+impl Default for Facing {
+    fn default() -> Self {
+        Self(LookDir::Up)
     }
-    if kb_input.pressed(KeyCode::KeyS) {
-        last_dir.0 = Some(-Vec2::Y);
+}
+
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Moving(pub bool);
+
+/// Turning assistance: after a turn the tank is steered onto the nearest lane across
+/// its movement, so it fits into gaps instead of catching brick corners.
+/// Lanes are `laneSize` apart, see [`TankArchetype::lane_size`].
+#[derive(Component, Default, Clone, Copy, Debug, PartialEq)]
+pub struct LaneAlignment {
+    /// Position on the lane the tank is being steered to, `None` once it got there.
+    pub target: Option<Vec2>,
+}
+
+#[derive(Bundle, Default)]
+pub struct MovementBundle {
+    intent: MoveIntent,
+    facing: Facing,
+    moving: Moving,
+    lane: LaneAlignment,
+}
+
+impl MovementBundle {
+    pub fn new(facing: LookDir) -> Self {
+        Self {
+            facing: Facing(facing),
+            ..Self::default()
+        }
     }
-    if kb_input.pressed(KeyCode::KeyA) {
-        last_dir.0 = Some(-Vec2::X);
+}
+
+/// Moves `position` onto the nearest lane across `direction`.
+/// Lanes are `lane` apart and go through `origin`.
+pub fn snap_to_lane(position: Vec2, direction: Vec2, origin: Vec2, lane: f32) -> Vec2 {
+    let snap = |value: f32, origin: f32| origin + ((value - origin) / lane).round() * lane;
+
+    if direction.x != 0.0 {
+        Vec2::new(position.x, snap(position.y, origin.y))
+    } else if direction.y != 0.0 {
+        Vec2::new(snap(position.x, origin.x), position.y)
+    } else {
+        position
     }
-    if kb_input.pressed(KeyCode::KeyD) {
-        last_dir.0 = Some(Vec2::X);
+}
+
+/// Velocity along `direction` plus the sideways part bringing the tank `offset` closer
+/// to its lane, never faster than `speed` in total.
+pub fn steer_to_lane(direction: Vec2, speed: f32, offset: Vec2, dt: f32) -> Vec2 {
+    let forward = direction.normalize_or_zero() * speed;
+    if dt <= 0.0 {
+        return forward;
     }
 
-    // проверяем: всё ещё зажата ли последняя клавиша
-    let mut direction = Vec2::ZERO;
+    (forward + (offset / dt).clamp_length_max(speed)).clamp_length_max(speed)
+}
 
-    if let Some(dir) = last_dir.0 {
-        let still_pressed = match dir {
-            d if d == Vec2::Y => kb_input.pressed(KeyCode::KeyW),
-            d if d == -Vec2::Y => kb_input.pressed(KeyCode::KeyS),
-            d if d == -Vec2::X => kb_input.pressed(KeyCode::KeyA),
-            d if d == Vec2::X => kb_input.pressed(KeyCode::KeyD),
-            _ => false,
-        };
+pub fn player_move_intent(
+    mut players: Query<(&ActionState, &mut MoveIntent), With<Player>>,
+    bindings: Res<InputBindings>,
+) {
+    for (actions, mut intent) in players.iter_mut() {
+        // из нескольких зажатых направлений выбирает политика (по умолчанию — последнее нажатое)
+        intent.set_if_neq(MoveIntent(actions.move_direction(bindings.move_policy)));
+    }
+}
 
-        if still_pressed {
-            direction = dir;
-        } else {
-            // последнюю отпустили — ищем другую нажатую
-            if kb_input.pressed(KeyCode::KeyW) {
-                direction = Vec2::Y;
-                last_dir.0 = Some(direction);
-            } else if kb_input.pressed(KeyCode::KeyS) {
-                direction = -Vec2::Y;
-                last_dir.0 = Some(direction);
-            } else if kb_input.pressed(KeyCode::KeyA) {
-                direction = -Vec2::X;
-                last_dir.0 = Some(direction);
-            } else if kb_input.pressed(KeyCode::KeyD) {
-                direction = Vec2::X;
-                last_dir.0 = Some(direction);
-            } else {
-                last_dir.0 = None;
+/// Destructible layers, the lanes follow their bricks.
+type BrickLayers<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static TilemapSize,
+        &'static TilemapGridSize,
+        &'static TilemapTileSize,
+        &'static TilemapType,
+        &'static TilemapAnchor,
+        &'static GlobalTransform,
+    ),
+    With<AffectedByDestructor>,
+>;
+
+#[allow(clippy::type_complexity)]
+pub fn apply_move_intent(
+    mut actors: Query<
+        (
+            &MoveIntent,
+            &mut LinearVelocity,
+            &Position,
+            &mut Facing,
+            &mut Moving,
+            &mut LaneAlignment,
+            Option<&TankArchetype>,
+        ),
+        (Without<Dead>, Without<Deactivated>),
+    >,
+    time: Res<Time>,
+    layers: BrickLayers,
+) {
+    let dt = time.delta_secs();
+
+    for (intent, mut vel, position, mut facing, mut moving, mut lane, archetype) in
+        actors.iter_mut()
+    {
+        let direction = intent.0;
+
+        // поворот на другую ось — выбираем полосу, к которой будем подруливать
+        if let Some(dir) = LookDir::from_vec2(direction) {
+            if dir.to_vec2().dot(facing.0.to_vec2()) == 0.0 {
+                let lane_size = archetype.map_or(0.0, |archetype| archetype.lane_size);
+                lane.target = if lane_size > 0.0 {
+                    lane_origin(&layers, position.0)
+                        .map(|origin| snap_to_lane(position.0, direction, origin, lane_size))
+                } else {
+                    None
+                };
             }
-        }
-    }
 
-    // если реально движемся — обновляем направление взгляда
-    if direction != Vec2::ZERO {
-        look_dir.0 = Some(match direction {
-            d if d == Vec2::Y => LookDir::Up,
-            d if d == -Vec2::Y => LookDir::Down,
-            d if d == -Vec2::X => LookDir::Left,
-            d if d == Vec2::X => LookDir::Right,
-            _ => unreachable!(),
-        });
-    }
+            facing.set_if_neq(Facing(dir));
+        }
 
-    let now_moving = direction != Vec2::ZERO;
-    moving.is_moving = now_moving;
-    // применяем скорость
+        moving.set_if_neq(Moving(direction != Vec2::ZERO));
 
-    for mut vel in player.iter_mut() {
+        // применяем скорость
         if direction == Vec2::ZERO {
             vel.0 = Vec2::ZERO; // <- ВАЖНО
-        } else {
-            vel.0 = direction * PLAYER_SPEED;
+            lane.target = None;
+            continue;
         }
+
+        let speed = archetype.map_or(PLAYER_SPEED, TankArchetype::movement_speed);
+
+        // доводим до полосы скоростью, а не телепортом, чтобы не въехать в стену;
+        // вдоль движения полоса не тянет
+        let across = facing.0.to_vec2().perp().abs();
+        let offset = lane
+            .target
+            .map_or(Vec2::ZERO, |target| (target - position.0) * across);
+        if offset.length() <= LANE_TOLERANCE {
+            lane.target = None;
+        }
+
+        vel.0 = match lane.target {
+            Some(_) => steer_to_lane(direction, speed, offset, dt),
+            None => direction.normalize() * speed,
+        };
     }
 }
 
-pub fn apply_player_look_dir(
-    look_dir: Res<PlayerLookDir>,
-    mut player: Query<&mut Transform, With<Player>>,
-) {
-    let Some(dir) = look_dir.0 else { return };
+/// Corner of the brick layer under `position`, lanes are counted from it.
+fn lane_origin(layers: &BrickLayers, position: Vec2) -> Option<Vec2> {
+    layers
+        .iter()
+        .find(|&(&size, &grid, &tile, &map_type, &anchor, &transform)| {
+            get_tile_pos_from_world_pos(transform, position, size, grid, tile, map_type, anchor)
+                .is_some()
+        })
+        .map(|(size, grid, tile, map_type, anchor, transform)| {
+            let center = TilePos::new(0, 0).center_in_world(size, grid, tile, map_type, anchor);
+            let corner = center - Vec2::new(grid.x, grid.y) / 2.0;
+            transform.transform_point(corner.extend(0.0)).truncate()
+        })
+}
+
+pub fn apply_facing(mut actors: Query<(&Facing, &mut Transform), Changed<Facing>>) {
+    for (facing, mut transform) in actors.iter_mut() {
+        transform.rotation = facing.0.to_rotation();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snaps_across_the_movement_only() {
+        let position = Vec2::new(13.0, 21.0);
+
+        assert_eq!(
+            snap_to_lane(position, Vec2::X, Vec2::ZERO, 8.0),
+            Vec2::new(13.0, 24.0)
+        );
+        assert_eq!(
+            snap_to_lane(position, -Vec2::Y, Vec2::ZERO, 8.0),
+            Vec2::new(16.0, 21.0)
+        );
+        assert_eq!(
+            snap_to_lane(position, Vec2::ZERO, Vec2::ZERO, 8.0),
+            position
+        );
+    }
+
+    #[test]
+    fn lanes_go_through_the_map_origin() {
+        let origin = Vec2::new(-100.0, 3.0);
+
+        assert_eq!(
+            snap_to_lane(Vec2::new(0.0, 12.0), Vec2::X, origin, 8.0),
+            Vec2::new(0.0, 11.0)
+        );
+        assert_eq!(
+            snap_to_lane(Vec2::new(-93.0, 0.0), Vec2::Y, origin, 8.0),
+            Vec2::new(-92.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn nudge_is_at_most_half_a_lane() {
+        let origin = Vec2::new(4.5, -2.0);
+
+        for step in 0..64 {
+            let position = Vec2::splat(step as f32 * 0.37);
+            for direction in [Vec2::X, Vec2::Y] {
+                let nudge = snap_to_lane(position, direction, origin, 8.0) - position;
+                assert!(nudge.length() <= 4.0 + 1e-4, "{position} {nudge}");
+            }
+        }
+    }
+
+    #[test]
+    fn steering_never_speeds_the_tank_up() {
+        for offset in [Vec2::ZERO, Vec2::new(0.0, 0.1), Vec2::new(0.0, -3.9)] {
+            for dt in [1.0 / 64.0, 0.5] {
+                let velocity = steer_to_lane(Vec2::X, 2.0, offset, dt);
+                assert!(velocity.length() <= 2.0 + 1e-4, "{offset} {dt} {velocity}");
+                assert!(velocity.x > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn steering_heads_for_the_lane() {
+        let velocity = steer_to_lane(Vec2::X, 2.0, Vec2::new(0.0, 3.0), 1.0 / 64.0);
+        assert!(velocity.y > 0.0);
 
-    let rotation = match dir {
-        LookDir::Up => 0.0,
-        LookDir::Right => -std::f32::consts::FRAC_PI_2,
-        LookDir::Down => std::f32::consts::PI,
-        LookDir::Left => std::f32::consts::FRAC_PI_2,
-    };
+        // Медленно отъехать на полосу, не перелетев её
+        let velocity = steer_to_lane(Vec2::X, 2.0, Vec2::new(0.0, -0.01), 1.0);
+        assert!((velocity.y + 0.01).abs() < 1e-4, "{velocity}");
 
-    for mut transform in player.iter_mut() {
-        transform.rotation = Quat::from_rotation_z(rotation);
+        assert_eq!(
+            steer_to_lane(Vec2::Y, 2.0, Vec2::ZERO, 0.1),
+            Vec2::new(0.0, 2.0)
+        );
     }
 }
@@ -1,36 +1,23 @@
 use avian2d::prelude::{
-    AngularDamping, Collider, CollidingEntities, CollisionEventsEnabled, CollisionLayers,
-    LinearDamping, LockedAxes, RigidBody,
+    Collider, CollidingEntities, CollisionEventsEnabled, CollisionLayers, LockedAxes, RigidBody,
 };
 use bevy::prelude::*;
-use bevy_ecs_tiled::prelude::*;
-use bevy_spritesheet_animation::prelude::{Animation, SpritesheetAnimation};
+use bevy_spritesheet_animation::prelude::SpritesheetAnimation;
 
-use crate::{
-    game::{GameLayer, actors::movement::PlayerMoving, map_objects::spawn::PlayerSpawn},
-    utils::tiled::tileset_reader::read_sprite_animation_from_tileset,
+use crate::game::{
+    GameLayer,
+    actors::{
+        archetype::{DEFAULT_PLAYER_ARCHETYPE, TankSpawner},
+        movement::Moving,
+        team::Team,
+    },
+    controls::InputSlot,
+    map_objects::spawn::PlayerSpawn,
 };
 
 #[derive(Component)]
 pub struct Player;
 
-#[derive(Bundle)]
-pub struct PlayerBundle {
-    player: Player,
-    transform: Transform,
-    collision_data: CollisionDataBundle,
-}
-
-impl PlayerBundle {
-    pub fn new(x: f32, y: f32, z: f32) -> Self {
-        Self {
-            player: Player,
-            transform: Transform::from_xyz(x, y, z),
-            collision_data: CollisionDataBundle::new(),
-        }
-    }
-}
-
 #[derive(Bundle)]
 pub struct CollisionDataBundle {
     rigid_body: RigidBody,
@@ -55,6 +42,8 @@ impl CollisionDataBundle {
                     GameLayer::Player,
                     GameLayer::Bricks,
                     GameLayer::Trees,
+                    GameLayer::Projectile,
+                    GameLayer::Charge,
                 ],
             ),
             locked_axes: LockedAxes::ROTATION_LOCKED,
@@ -64,69 +53,42 @@ impl CollisionDataBundle {
 
 pub fn spawn_player(
     mut commands: Commands,
-    tiled_map_assets: Res<Assets<TiledMapAsset>>,
     existing_player: Option<Single<Entity, With<Player>>>,
-    animations: ResMut<Assets<Animation>>,
-    atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    images: ResMut<Assets<Image>>,
-    query_player_spawns: Query<(&Transform, &GlobalTransform), With<PlayerSpawn>>,
+    mut tank_spawner: TankSpawner,
+    query_player_spawns: Query<(Entity, &GlobalTransform), With<PlayerSpawn>>,
 ) {
-    if existing_player.is_some() || query_player_spawns.is_empty() {
+    if existing_player.is_some() || query_player_spawns.is_empty() || !tank_spawner.is_ready() {
         return;
     }
 
-    let Ok((tr, spawn_transform)) = query_player_spawns.single() else {
+    let Ok((spawn, spawn_transform)) = query_player_spawns.single() else {
         return;
     };
 
-    let Some((sprite, animation)) = read_sprite_animation_from_tileset(
-        "tank".to_string(),
-        0,
-        tiled_map_assets,
-        animations,
-        atlas_layouts,
-        images,
-    ) else {
+    let position = Vec3::new(
+        spawn_transform.translation().x,
+        spawn_transform.translation().y,
+        -150.0,
+    );
+
+    let Some(id) = tank_spawner.spawn(&mut commands, DEFAULT_PLAYER_ARCHETYPE, position) else {
         return;
     };
 
-    //===================
-    // !!! Example usage:
-    //
-    // let Some(sprite) =
-    //     read_sprite_from_tileset("tank".to_string(), 0, tiled_map_assets, atlas_layouts)
-    // else {
-    //     return;
-    // };
-
-    let id = commands
-        .spawn(PlayerBundle::new(
-            spawn_transform.translation().x,
-            spawn_transform.translation().y,
-            -150.0,
-        ))
-        .id();
     //commands.entity(id).insert(TileDestructor::default());
-    commands.entity(id).insert((
-        sprite,
-        animation,
-        LinearDamping(10.0),
-        AngularDamping(0.0),
-        //        MaxLinearSpeed(50.0),
-    ));
+    commands
+        .entity(id)
+        .insert((Player, Team::PLAYERS, InputSlot(0)));
+    // Точка спавна одноразовая, иначе после смерти игрок тут же появится снова
+    commands.entity(spawn).remove::<PlayerSpawn>();
 }
 
-pub fn player_animation_controller(
-    moving: Res<PlayerMoving>,
-    mut query: Query<&mut SpritesheetAnimation, With<Player>>,
+pub fn movement_animation_controller(
+    mut query: Query<(&Moving, &mut SpritesheetAnimation), Changed<Moving>>,
 ) {
-    // если состояние не менялось — вообще ничего не делаем
-    if !moving.is_changed() {
-        return;
-    }
-
-    for mut animation in query.iter_mut() {
-        if moving.is_moving {
+    // только те, у кого состояние поменялось
+    for (moving, mut animation) in query.iter_mut() {
+        if moving.0 {
             animation.play();
         } else {
             animation.pause();
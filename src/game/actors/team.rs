@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+
+pub struct TeamPlugin;
+
+impl Plugin for TeamPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FriendlyFire>().register_type::<Team>();
+    }
+}
+
+/// Side an actor fights for. Projectiles get the team of their shooter when fired.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Team(pub u32);
+
+impl Team {
+    pub const PLAYERS: Team = Team(0);
+    pub const ENEMIES: Team = Team(1);
+}
+
+/// What a projectile does when it touches a body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitRule {
+    /// Flies through as if the body wasn't there.
+    Ignore,
+    Damage,
+    /// Stops without dealing damage.
+    Stop,
+}
+
+/// Friendly fire rules. Co-op keeps the defaults, versus modes put players into
+/// different teams or turn `same_team` into [`HitRule::Damage`].
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct FriendlyFire {
+    pub same_team: HitRule,
+    /// The projectile touches the one who fired it, e.g. after a ricochet.
+    pub shooter: HitRule,
+}
+
+impl Default for FriendlyFire {
+    fn default() -> Self {
+        Self {
+            same_team: HitRule::Stop,
+            shooter: HitRule::Ignore,
+        }
+    }
+}
+
+impl FriendlyFire {
+    pub fn rule(
+        &self,
+        shooter: Option<Entity>,
+        projectile_team: Option<Team>,
+        target: Entity,
+        target_team: Option<Team>,
+    ) -> HitRule {
+        if shooter == Some(target) {
+            return self.shooter;
+        }
+
+        match (projectile_team, target_team) {
+            (Some(projectile_team), Some(target_team)) if projectile_team == target_team => {
+                self.same_team
+            }
+            _ => HitRule::Damage,
+        }
+    }
+
+    /// Whether two projectiles fly through each other instead of cancelling out.
+    /// Pellets of one shot always pass, projectiles of one team pass unless
+    /// friendly fire is on.
+    pub fn projectiles_pass(
+        &self,
+        shooter: Option<Entity>,
+        team: Option<Team>,
+        other_shooter: Option<Entity>,
+        other_team: Option<Team>,
+    ) -> bool {
+        if shooter == other_shooter {
+            return true;
+        }
+
+        team.is_some() && team == other_team && self.same_team != HitRule::Damage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_rules() {
+        let mut world = World::new();
+        let shooter = world.spawn_empty().id();
+        let target = world.spawn_empty().id();
+        let friendly_fire = FriendlyFire::default();
+
+        assert_eq!(
+            friendly_fire.rule(
+                Some(shooter),
+                Some(Team::PLAYERS),
+                shooter,
+                Some(Team::PLAYERS)
+            ),
+            HitRule::Ignore
+        );
+        assert_eq!(
+            friendly_fire.rule(
+                Some(shooter),
+                Some(Team::PLAYERS),
+                target,
+                Some(Team::PLAYERS)
+            ),
+            HitRule::Stop
+        );
+        assert_eq!(
+            friendly_fire.rule(
+                Some(shooter),
+                Some(Team::PLAYERS),
+                target,
+                Some(Team::ENEMIES)
+            ),
+            HitRule::Damage
+        );
+        // Без команды попадание всегда наносит урон
+        assert_eq!(
+            friendly_fire.rule(Some(shooter), None, target, None),
+            HitRule::Damage
+        );
+    }
+
+    #[test]
+    fn versus_mode_damages_teammates() {
+        let mut world = World::new();
+        let shooter = world.spawn_empty().id();
+        let target = world.spawn_empty().id();
+        let friendly_fire = FriendlyFire {
+            same_team: HitRule::Damage,
+            ..default()
+        };
+
+        assert_eq!(
+            friendly_fire.rule(
+                Some(shooter),
+                Some(Team::PLAYERS),
+                target,
+                Some(Team::PLAYERS)
+            ),
+            HitRule::Damage
+        );
+    }
+
+    #[test]
+    fn projectiles_of_one_shooter_pass() {
+        let shooter = World::new().spawn_empty().id();
+        let friendly_fire = FriendlyFire {
+            same_team: HitRule::Damage,
+            ..default()
+        };
+
+        assert!(friendly_fire.projectiles_pass(
+            Some(shooter),
+            Some(Team::PLAYERS),
+            Some(shooter),
+            Some(Team::PLAYERS),
+        ));
+    }
+
+    #[test]
+    fn projectiles_of_one_team_pass_unless_friendly_fire() {
+        let mut world = World::new();
+        let first = Some(world.spawn_empty().id());
+        let second = Some(world.spawn_empty().id());
+        let team = Some(Team::PLAYERS);
+
+        assert!(FriendlyFire::default().projectiles_pass(first, team, second, team));
+        assert!(
+            !FriendlyFire {
+                same_team: HitRule::Damage,
+                ..default()
+            }
+            .projectiles_pass(first, team, second, team)
+        );
+        assert!(!FriendlyFire::default().projectiles_pass(
+            first,
+            team,
+            second,
+            Some(Team::ENEMIES)
+        ));
+        assert!(!FriendlyFire::default().projectiles_pass(first, None, second, None));
+    }
+}
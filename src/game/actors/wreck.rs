@@ -0,0 +1,98 @@
+use avian2d::prelude::{LinearVelocity, RigidBody, Sensor};
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::TiledMapAsset;
+use bevy_spritesheet_animation::prelude::SpritesheetAnimation;
+
+use crate::{
+    game::{
+        actors::{
+            enemy::{Enemy, EnemyAi},
+            health::{DeathEvent, HealthSet, KeepOnDeath},
+            movement::MoveIntent,
+            player::Player,
+        },
+        controls::{ActionState, InputSlot},
+        weapons::{ammo::Ammo, charge::SecondaryWeapon, weapon::Weapon},
+    },
+    utils::tiled::tileset_reader::read_sprite_from_tileset,
+};
+
+pub struct WreckPlugin;
+
+impl Plugin for WreckPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WreckSettings>()
+            .add_systems(FixedPostUpdate, turn_into_wreck.in_set(HealthSet::Cleanup));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WreckMode {
+    /// Wrecks block tanks and bullets.
+    #[default]
+    Obstacle,
+    /// Wrecks are only decoration, everything passes through.
+    Sensor,
+}
+
+#[derive(Resource, Default)]
+pub struct WreckSettings {
+    pub mode: WreckMode,
+}
+
+/// Tile to show instead of the entity once it dies, e.g. `simple_wreck` from `tank.tsx`.
+#[derive(Component, Clone, Debug)]
+#[require(KeepOnDeath)]
+pub struct LeavesWreck {
+    pub tileset: String,
+    pub tile_id: u32,
+}
+
+#[derive(Component)]
+pub struct Wreck;
+
+fn turn_into_wreck(
+    mut commands: Commands,
+    mut death_events: MessageReader<DeathEvent>,
+    wrecks: Query<&LeavesWreck>,
+    settings: Res<WreckSettings>,
+    tiled_map_assets: Res<Assets<TiledMapAsset>>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    for event in death_events.read() {
+        let Ok(wreck) = wrecks.get(event.entity) else {
+            continue;
+        };
+
+        let mut entity_commands = commands.entity(event.entity);
+        entity_commands
+            .remove::<(
+                Player,
+                Enemy,
+                EnemyAi,
+                MoveIntent,
+                SpritesheetAnimation,
+                LeavesWreck,
+                // Обломки не стреляют и не слушают ввод
+                Weapon,
+                Ammo,
+                SecondaryWeapon,
+                InputSlot,
+                ActionState,
+            )>()
+            .insert((Wreck, RigidBody::Static, LinearVelocity(Vec2::ZERO)));
+
+        if settings.mode == WreckMode::Sensor {
+            entity_commands.insert(Sensor);
+        }
+
+        if let Some(sprite) = read_sprite_from_tileset(
+            wreck.tileset.clone(),
+            wreck.tile_id,
+            &tiled_map_assets,
+            &mut atlas_layouts,
+        ) {
+            entity_commands.insert(sprite);
+        }
+    }
+}
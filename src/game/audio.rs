@@ -0,0 +1,378 @@
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    game::{
+        actors::{
+            health::{DeathEvent, HealthSet},
+            movement::Moving,
+            player::Player,
+        },
+        state::GameState,
+        weapons::weapon::ShotFired,
+    },
+    utils::tiled::destructor::TileHit,
+};
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        add_cue_sources(app);
+
+        app.init_resource::<AudioVolumes>()
+            .add_systems(Startup, start_music)
+            .add_systems(PostUpdate, (stop_cues, play_cues).chain());
+    }
+}
+
+/// Systems turning gameplay messages into cues, without any audio output.
+fn add_cue_sources(app: &mut App) {
+    app.add_message::<PlayCue>()
+        .add_message::<StopCue>()
+        .init_resource::<MovementCue>()
+        .add_systems(
+            FixedPostUpdate,
+            death_cues
+                .after(HealthSet::Damage)
+                .before(HealthSet::Cleanup),
+        )
+        // Выход из Playing бывает и при паузе
+        .add_systems(OnExit(GameState::Playing), stop_movement_cue)
+        // Цепочкой, чтобы звуки одного кадра шли в одном и том же порядке
+        .add_systems(
+            Update,
+            (
+                shot_cues,
+                tile_hit_cues,
+                movement_cues.run_if(in_state(GameState::Playing)),
+            )
+                .chain(),
+        );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundCue {
+    IntroMusic,
+    PlayerFire,
+    EnemyFire,
+    BulletHitWall,
+    BulletHitStrongWall,
+    ExplosionEnemy,
+    ExplosionPlayer,
+    MovePlayer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeCategory {
+    Music,
+    Effects,
+    Movement,
+}
+
+impl SoundCue {
+    pub fn path(self) -> &'static str {
+        match self {
+            SoundCue::IntroMusic => "audio/music/intro.m4a",
+            SoundCue::PlayerFire | SoundCue::EnemyFire => "audio/sfx/player_fire_bullet.m4a",
+            SoundCue::BulletHitWall => "audio/sfx/player_bullet_wall.m4a",
+            SoundCue::BulletHitStrongWall => "audio/sfx/player_bullet_strong_wall.m4a",
+            SoundCue::ExplosionEnemy => "audio/sfx/explosion_enemy.m4a",
+            SoundCue::ExplosionPlayer => "audio/sfx/explosion_player.m4a",
+            SoundCue::MovePlayer => "audio/sfx/move_player.m4a",
+        }
+    }
+
+    pub fn category(self) -> VolumeCategory {
+        match self {
+            SoundCue::IntroMusic => VolumeCategory::Music,
+            SoundCue::MovePlayer => VolumeCategory::Movement,
+            _ => VolumeCategory::Effects,
+        }
+    }
+
+    /// Looping cues keep playing until a [`StopCue`] for them arrives.
+    pub fn is_looping(self) -> bool {
+        matches!(self, SoundCue::IntroMusic | SoundCue::MovePlayer)
+    }
+
+    /// Extra volume factor on top of the category volume.
+    fn gain(self) -> f32 {
+        match self {
+            SoundCue::EnemyFire => 0.5,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Gameplay asks for a sound. Headless builds can read these without any audio output.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlayCue(pub SoundCue);
+
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StopCue(pub SoundCue);
+
+#[derive(Resource)]
+pub struct AudioVolumes {
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+    pub movement: f32,
+}
+
+impl Default for AudioVolumes {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 0.5,
+            effects: 0.8,
+            movement: 0.4,
+        }
+    }
+}
+
+impl AudioVolumes {
+    pub fn get(&self, category: VolumeCategory) -> f32 {
+        let volume = match category {
+            VolumeCategory::Music => self.music,
+            VolumeCategory::Effects => self.effects,
+            VolumeCategory::Movement => self.movement,
+        };
+
+        self.master * volume
+    }
+}
+
+#[derive(Component)]
+struct LoopingCue(SoundCue);
+
+/// Whether the player engine sound was started and not stopped yet.
+#[derive(Resource, Default)]
+struct MovementCue {
+    playing: bool,
+}
+
+fn start_music(mut cues: MessageWriter<PlayCue>) {
+    cues.write(PlayCue(SoundCue::IntroMusic));
+}
+
+fn shot_cues(
+    mut shots: MessageReader<ShotFired>,
+    players: Query<(), With<Player>>,
+    mut cues: MessageWriter<PlayCue>,
+) {
+    for shot in shots.read() {
+        let cue = if players.contains(shot.shooter) {
+            SoundCue::PlayerFire
+        } else {
+            SoundCue::EnemyFire
+        };
+        cues.write(PlayCue(cue));
+    }
+}
+
+fn tile_hit_cues(mut tile_hits: MessageReader<TileHit>, mut cues: MessageWriter<PlayCue>) {
+    for hit in tile_hits.read() {
+        let cue = if hit.destroyed {
+            SoundCue::BulletHitWall
+        } else {
+            SoundCue::BulletHitStrongWall
+        };
+        cues.write(PlayCue(cue));
+    }
+}
+
+fn death_cues(
+    mut death_events: MessageReader<DeathEvent>,
+    players: Query<(), With<Player>>,
+    mut cues: MessageWriter<PlayCue>,
+) {
+    for event in death_events.read() {
+        let cue = if players.contains(event.entity) {
+            SoundCue::ExplosionPlayer
+        } else {
+            SoundCue::ExplosionEnemy
+        };
+        cues.write(PlayCue(cue));
+    }
+}
+
+fn movement_cues(
+    player: Option<Single<&Moving, With<Player>>>,
+    mut cue: ResMut<MovementCue>,
+    mut play: MessageWriter<PlayCue>,
+    mut stop: MessageWriter<StopCue>,
+) {
+    let is_moving = player.is_some_and(|moving| moving.0);
+    if is_moving == cue.playing {
+        return;
+    }
+    cue.playing = is_moving;
+
+    if is_moving {
+        play.write(PlayCue(SoundCue::MovePlayer));
+    } else {
+        stop.write(StopCue(SoundCue::MovePlayer));
+    }
+}
+
+/// Silences the engine on pause or game over, it starts again once the player drives on.
+fn stop_movement_cue(mut cue: ResMut<MovementCue>, mut stop: MessageWriter<StopCue>) {
+    if cue.playing {
+        cue.playing = false;
+        stop.write(StopCue(SoundCue::MovePlayer));
+    }
+}
+
+fn play_cues(
+    mut commands: Commands,
+    mut cues: MessageReader<PlayCue>,
+    asset_server: Res<AssetServer>,
+    volumes: Res<AudioVolumes>,
+    looping: Query<&LoopingCue>,
+) {
+    for PlayCue(cue) in cues.read().copied() {
+        let volume = Volume::Linear(volumes.get(cue.category()) * cue.gain());
+        let player = AudioPlayer::new(asset_server.load(cue.path()));
+
+        if cue.is_looping() {
+            if looping.iter().any(|playing| playing.0 == cue) {
+                continue;
+            }
+            commands.spawn((
+                player,
+                PlaybackSettings::LOOP.with_volume(volume),
+                LoopingCue(cue),
+            ));
+        } else {
+            commands.spawn((player, PlaybackSettings::DESPAWN.with_volume(volume)));
+        }
+    }
+}
+
+fn stop_cues(
+    mut commands: Commands,
+    mut cues: MessageReader<StopCue>,
+    looping: Query<(Entity, &LoopingCue)>,
+) {
+    for StopCue(cue) in cues.read().copied() {
+        for (entity, playing) in looping.iter() {
+            if playing.0 == cue {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::message::Messages, state::app::StatesPlugin};
+    use bevy_ecs_tiled::prelude::TilePos;
+
+    use super::*;
+
+    fn cue_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<GameState>()
+            .add_message::<ShotFired>()
+            .add_message::<TileHit>()
+            .add_message::<DeathEvent>();
+        add_cue_sources(&mut app);
+        app
+    }
+
+    fn enter(app: &mut App, state: GameState) {
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(state);
+        app.update();
+    }
+
+    fn played(app: &mut App) -> Vec<SoundCue> {
+        app.world_mut()
+            .resource_mut::<Messages<PlayCue>>()
+            .drain()
+            .map(|cue| cue.0)
+            .collect()
+    }
+
+    fn stopped(app: &mut App) -> Vec<SoundCue> {
+        app.world_mut()
+            .resource_mut::<Messages<StopCue>>()
+            .drain()
+            .map(|cue| cue.0)
+            .collect()
+    }
+
+    #[test]
+    fn shots_and_tile_hits_play_effects() {
+        let mut app = cue_app();
+        let player = app.world_mut().spawn(Player).id();
+        let enemy = app.world_mut().spawn_empty().id();
+
+        for shooter in [player, enemy] {
+            app.world_mut().write_message(ShotFired {
+                shooter,
+                position: Vec2::ZERO,
+                direction: Vec2::Y,
+            });
+        }
+        for destroyed in [true, false] {
+            app.world_mut().write_message(TileHit {
+                position: Vec2::ZERO,
+                tilemap: Entity::PLACEHOLDER,
+                tile_pos: TilePos::new(0, 0),
+                destroyed,
+            });
+        }
+        app.update();
+
+        let cues = played(&mut app);
+        assert_eq!(
+            cues,
+            vec![
+                SoundCue::PlayerFire,
+                SoundCue::EnemyFire,
+                SoundCue::BulletHitWall,
+                SoundCue::BulletHitStrongWall,
+            ]
+        );
+        assert!(
+            cues.iter()
+                .all(|cue| cue.category() == VolumeCategory::Effects && !cue.is_looping())
+        );
+    }
+
+    #[test]
+    fn movement_loops_while_playing() {
+        let mut app = cue_app();
+        let player = app.world_mut().spawn((Player, Moving(true))).id();
+
+        // В меню звука движения нет
+        enter(&mut app, GameState::MainMenu);
+        assert!(played(&mut app).is_empty());
+
+        enter(&mut app, GameState::Playing);
+        let cues = played(&mut app);
+        assert_eq!(cues, vec![SoundCue::MovePlayer]);
+        assert_eq!(cues[0].category(), VolumeCategory::Movement);
+        assert!(cues[0].is_looping());
+
+        app.world_mut().get_mut::<Moving>(player).unwrap().0 = false;
+        app.update();
+        assert_eq!(stopped(&mut app), vec![SoundCue::MovePlayer]);
+
+        app.world_mut().get_mut::<Moving>(player).unwrap().0 = true;
+        app.update();
+        assert_eq!(played(&mut app), vec![SoundCue::MovePlayer]);
+
+        enter(&mut app, GameState::Paused);
+        assert_eq!(stopped(&mut app), vec![SoundCue::MovePlayer]);
+        app.update();
+        assert!(played(&mut app).is_empty());
+
+        // После паузы двигатель снова слышен
+        enter(&mut app, GameState::Playing);
+        assert_eq!(played(&mut app), vec![SoundCue::MovePlayer]);
+    }
+}
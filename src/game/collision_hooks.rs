@@ -0,0 +1,172 @@
+use std::collections::HashSet;
+
+use avian2d::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_tiled::prelude::*;
+
+use crate::{
+    game::{
+        actors::{
+            health::Health,
+            team::{FriendlyFire, HitRule, Team},
+        },
+        weapons::bullet::{Bullet, BulletData},
+    },
+    utils::tiled::{destructor::get_tile_pos_from_world_pos, tileset_reader::read_bool_property},
+};
+
+/// How deep behind the contact point we look for the tile that was actually hit.
+const TILE_SAMPLE_DEPTH: f32 = 1.0;
+
+pub struct GameCollisionHooksPlugin;
+
+impl Plugin for GameCollisionHooksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(on_tile_created);
+    }
+}
+
+/// Positions of tiles with `allowBullet = true` in a tile layer.
+#[derive(Component, Default)]
+pub struct BulletPassableTiles {
+    tiles: HashSet<TilePos>,
+}
+
+impl BulletPassableTiles {
+    pub fn contains(&self, pos: &TilePos) -> bool {
+        self.tiles.contains(pos)
+    }
+}
+
+/// Physics hooks shared by all game colliders, registered with
+/// `PhysicsPlugins::with_collision_hooks`.
+///
+/// The layer collider covers every tile of a layer, so passable tiles are
+/// filtered here per contact. Passable tiles work best on the edge of a
+/// group of solid tiles, not surrounded by them.
+///
+/// Bullets also fly through bodies that [`FriendlyFire`] tells them to ignore,
+/// and through friendly bullets so they don't knock each other off course.
+#[derive(SystemParam)]
+pub struct GameCollisionHooks<'w, 's> {
+    bullets: Query<'w, 's, (&'static BulletData, Option<&'static Team>), With<Bullet>>,
+    targets: Query<'w, 's, Option<&'static Team>, With<Health>>,
+    friendly_fire: Res<'w, FriendlyFire>,
+    colliders: Query<'w, 's, &'static ChildOf, With<Collider>>,
+    tilemaps: Query<
+        'w,
+        's,
+        (
+            &'static ChildOf,
+            &'static TilemapSize,
+            &'static TilemapGridSize,
+            &'static TilemapTileSize,
+            &'static TilemapType,
+            &'static GlobalTransform,
+            &'static TilemapAnchor,
+            &'static BulletPassableTiles,
+        ),
+    >,
+}
+
+impl CollisionHooks for GameCollisionHooks<'_, '_> {
+    fn modify_contacts(&self, contacts: &mut ContactPair, _commands: &mut Commands) -> bool {
+        // Нормаль направлена от первого коллайдера ко второму
+        let (bullet_collider, other_collider, normal_sign) =
+            if self.bullets.contains(contacts.collider1) {
+                (contacts.collider1, contacts.collider2, 1.0)
+            } else if self.bullets.contains(contacts.collider2) {
+                (contacts.collider2, contacts.collider1, -1.0)
+            } else {
+                return true;
+            };
+
+        if let Ok((bullet, team)) = self.bullets.get(bullet_collider) {
+            // Иначе дробь одного выстрела разлетается, столкнувшись в первом же кадре
+            if let Ok((other_bullet, other_team)) = self.bullets.get(other_collider) {
+                return !self.friendly_fire.projectiles_pass(
+                    bullet.parent,
+                    team.copied(),
+                    other_bullet.parent,
+                    other_team.copied(),
+                );
+            }
+
+            if let Ok(target_team) = self.targets.get(other_collider) {
+                let rule = self.friendly_fire.rule(
+                    bullet.parent,
+                    team.copied(),
+                    other_collider,
+                    target_team.copied(),
+                );
+                return rule != HitRule::Ignore;
+            }
+        }
+
+        let Ok(parent_of_collider) = self.colliders.get(other_collider) else {
+            return true;
+        };
+
+        for (parent_of_layer, &size, &grid, &tile, &map_type, &transform, &anchor, passable) in
+            self.tilemaps.iter()
+        {
+            if parent_of_collider != parent_of_layer {
+                continue;
+            }
+
+            contacts.manifolds.retain(|manifold| {
+                let into_tile = manifold.normal * normal_sign;
+
+                !manifold.points.iter().all(|point| {
+                    get_tile_pos_from_world_pos(
+                        transform,
+                        into_tile.mul_add(Vec2::splat(TILE_SAMPLE_DEPTH), point.point),
+                        size,
+                        grid,
+                        tile,
+                        map_type,
+                        anchor,
+                    )
+                    .is_some_and(|pos| passable.contains(&pos))
+                })
+            });
+        }
+
+        !contacts.manifolds.is_empty()
+    }
+}
+
+fn on_tile_created(
+    tile_created: On<TiledEvent<TileCreated>>,
+    assets: Res<Assets<TiledMapAsset>>,
+    mut commands: Commands,
+) {
+    let Some(tile) = tile_created.event().get_tile(&assets) else {
+        return;
+    };
+
+    if !read_bool_property(&tile.properties, "allowBullet").unwrap_or(false) {
+        return;
+    }
+
+    let (Some(tile_entity), Some(tilemap_entity)) = (
+        tile_created.event().get_tile_entity(),
+        tile_created.event().get_tilemap_entity(),
+    ) else {
+        return;
+    };
+
+    commands
+        .entity(tilemap_entity)
+        .entry::<BulletPassableTiles>()
+        .or_default();
+
+    commands.queue(move |world: &mut World| {
+        let Some(&pos) = world.get::<TilePos>(tile_entity) else {
+            return;
+        };
+        if let Some(mut passable) = world.get_mut::<BulletPassableTiles>(tilemap_entity) {
+            passable.tiles.insert(pos);
+        }
+    });
+}
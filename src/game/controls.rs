@@ -0,0 +1,644 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    asset::{AssetLoadFailedEvent, AssetLoader, LoadContext, io::Reader},
+    input::InputSystems,
+    prelude::*,
+};
+
+use crate::game::actors::movement::LookDir;
+
+/// Loaded through the asset server, the defaults stay in use when the file is missing or broken.
+const CONTROLS_PATH: &str = "config/controls.cfg";
+const DEFAULT_STICK_DEAD_ZONE: f32 = 0.3;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<InputBindings>()
+            .register_asset_loader(ControlsLoader)
+            .init_resource::<InputBindings>()
+            .init_resource::<GamepadSlots>()
+            .init_resource::<VirtualActions>()
+            .add_systems(Startup, load_bindings)
+            .add_systems(
+                PreUpdate,
+                (
+                    apply_loaded_bindings.before(UpdateActions),
+                    (assign_gamepads, update_action_states)
+                        .chain()
+                        .in_set(UpdateActions)
+                        .after(InputSystems),
+                ),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    /// Secondary weapon.
+    AltFire,
+    Reload,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::AltFire,
+        Action::Reload,
+        Action::Pause,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| format!("{action:?}") == name)
+    }
+
+    /// Unit vector of the movement actions.
+    pub fn direction(self) -> Option<Vec2> {
+        match self {
+            Action::MoveUp => Some(Vec2::Y),
+            Action::MoveDown => Some(-Vec2::Y),
+            Action::MoveLeft => Some(-Vec2::X),
+            Action::MoveRight => Some(Vec2::X),
+            _ => None,
+        }
+    }
+
+    fn from_look_dir(dir: LookDir) -> Action {
+        match dir {
+            LookDir::Up => Action::MoveUp,
+            LookDir::Down => Action::MoveDown,
+            LookDir::Left => Action::MoveLeft,
+            LookDir::Right => Action::MoveRight,
+        }
+    }
+}
+
+/// Keyboard key or gamepad button bound to an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Pad(GamepadButton),
+}
+
+/// Which of several held movement actions decides the direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MovePolicy {
+    /// The last pressed direction wins, releasing it falls back to the one held before.
+    #[default]
+    LastPressed,
+    /// The first held direction wins until it is released.
+    FirstPressed,
+}
+
+/// Keys and buttons of every action, one map per local player.
+/// Loaded from `controls.cfg` as an asset and copied into the resource.
+#[derive(Resource, Asset, TypePath, Debug, Clone)]
+pub struct InputBindings {
+    pub move_policy: MovePolicy,
+    /// Left stick deflection below this is ignored.
+    pub stick_dead_zone: f32,
+    pub slots: Vec<HashMap<Action, Vec<Binding>>>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use Binding::{Key, Pad};
+
+        let player1 = HashMap::from([
+            (
+                Action::MoveUp,
+                vec![Key(KeyCode::KeyW), Pad(GamepadButton::DPadUp)],
+            ),
+            (
+                Action::MoveDown,
+                vec![Key(KeyCode::KeyS), Pad(GamepadButton::DPadDown)],
+            ),
+            (
+                Action::MoveLeft,
+                vec![Key(KeyCode::KeyA), Pad(GamepadButton::DPadLeft)],
+            ),
+            (
+                Action::MoveRight,
+                vec![Key(KeyCode::KeyD), Pad(GamepadButton::DPadRight)],
+            ),
+            (
+                Action::Fire,
+                vec![Key(KeyCode::Space), Pad(GamepadButton::South)],
+            ),
+            (
+                Action::AltFire,
+                vec![Key(KeyCode::KeyE), Pad(GamepadButton::East)],
+            ),
+            (
+                Action::Reload,
+                vec![Key(KeyCode::KeyR), Pad(GamepadButton::West)],
+            ),
+            (
+                Action::Pause,
+                vec![Key(KeyCode::Escape), Pad(GamepadButton::Start)],
+            ),
+        ]);
+
+        Self {
+            move_policy: MovePolicy::default(),
+            stick_dead_zone: DEFAULT_STICK_DEAD_ZONE,
+            slots: vec![player1],
+        }
+    }
+}
+
+impl InputBindings {
+    /// Parses the `controls.cfg` format:
+    ///
+    /// ```text
+    /// move_policy = last_pressed
+    /// stick_dead_zone = 0.3
+    ///
+    /// [player1]
+    /// MoveUp = KeyW, ArrowUp, GamepadDPadUp
+    /// ```
+    ///
+    /// Key names are the names of [`KeyCode`] variants, buttons are [`GamepadButton`]
+    /// variants prefixed with `Gamepad`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut bindings = Self {
+            move_policy: MovePolicy::default(),
+            stick_dead_zone: DEFAULT_STICK_DEAD_ZONE,
+            slots: Vec::new(),
+        };
+        let mut slot = None;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: &str| format!("line {}: {message}", number + 1);
+
+            if let Some(section) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                let index = section
+                    .strip_prefix("player")
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .filter(|&index| index > 0)
+                    .ok_or_else(|| error("expected [playerN]"))?;
+
+                if bindings.slots.len() < index {
+                    bindings.slots.resize_with(index, HashMap::new);
+                }
+                slot = Some(index - 1);
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected name = value"))?;
+            let (name, value) = (name.trim(), value.trim());
+
+            let Some(slot) = slot else {
+                match (name, value) {
+                    ("move_policy", "last_pressed") => {
+                        bindings.move_policy = MovePolicy::LastPressed
+                    }
+                    ("move_policy", "first_pressed") => {
+                        bindings.move_policy = MovePolicy::FirstPressed
+                    }
+                    ("stick_dead_zone", value) => {
+                        bindings.stick_dead_zone =
+                            value.parse().map_err(|_| error("expected a number"))?
+                    }
+                    _ => return Err(error("unknown setting")),
+                }
+                continue;
+            };
+
+            let action = Action::from_name(name).ok_or_else(|| error("unknown action"))?;
+            let keys = value
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(|key| parse_binding(key).ok_or_else(|| error(&format!("unknown key {key}"))))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            bindings.slots[slot].insert(action, keys);
+        }
+
+        if bindings.slots.is_empty() {
+            return Err("no [playerN] section".to_string());
+        }
+
+        Ok(bindings)
+    }
+}
+
+/// [`ActionState`]s are refreshed here, systems feeding [`VirtualActions`] run before it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UpdateActions;
+
+/// Actions held through the on-screen controls, added to the first player slot.
+#[derive(Resource, Debug, Default)]
+pub struct VirtualActions(pub HashSet<Action>);
+
+/// Gamepad of every player slot, unassigned pads are given to the first free slot.
+#[derive(Resource, Debug, Default)]
+pub struct GamepadSlots(pub HashMap<usize, Entity>);
+
+/// Local player reading the bindings of `slot` in [`InputBindings`].
+#[derive(Component, Debug, Clone, Copy)]
+#[require(ActionState)]
+pub struct InputSlot(pub usize);
+
+/// Actions held by a player this frame. Gameplay reads this instead of the keyboard.
+#[derive(Component, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    /// Held movement actions, oldest first.
+    held_moves: Vec<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Replaces the state with the actions held this frame.
+    pub fn update(&mut self, held: HashSet<Action>) {
+        self.just_pressed = held.difference(&self.pressed).copied().collect();
+
+        self.held_moves.retain(|action| held.contains(action));
+        for &action in Action::ALL.iter() {
+            if action.direction().is_some() && self.just_pressed.contains(&action) {
+                self.held_moves.push(action);
+            }
+        }
+
+        self.pressed = held;
+    }
+
+    /// Direction of the winning movement action, zero when none is held.
+    pub fn move_direction(&self, policy: MovePolicy) -> Vec2 {
+        let action = match policy {
+            MovePolicy::LastPressed => self.held_moves.last(),
+            MovePolicy::FirstPressed => self.held_moves.first(),
+        };
+
+        action
+            .and_then(|action| action.direction())
+            .unwrap_or(Vec2::ZERO)
+    }
+}
+
+/// Reads `.cfg` files with [`InputBindings::parse`].
+#[derive(Default, TypePath)]
+struct ControlsLoader;
+
+impl AssetLoader for ControlsLoader {
+    type Asset = InputBindings;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        Ok(InputBindings::parse(std::str::from_utf8(&bytes)?)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["cfg"]
+    }
+}
+
+#[derive(Resource)]
+struct ControlsConfig(Handle<InputBindings>);
+
+fn load_bindings(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ControlsConfig(asset_server.load(CONTROLS_PATH)));
+}
+
+/// Replaces the bindings once the file is loaded, and again when it changes on disk.
+fn apply_loaded_bindings(
+    mut events: MessageReader<AssetEvent<InputBindings>>,
+    mut failures: MessageReader<AssetLoadFailedEvent<InputBindings>>,
+    config: Res<ControlsConfig>,
+    assets: Res<Assets<InputBindings>>,
+    mut bindings: ResMut<InputBindings>,
+) {
+    for event in events.read() {
+        if (event.is_loaded_with_dependencies(&config.0) || event.is_modified(&config.0))
+            && let Some(loaded) = assets.get(&config.0)
+        {
+            *bindings = loaded.clone();
+        }
+    }
+
+    for failure in failures.read() {
+        warn!(
+            "Can't load {}: {}, using default controls",
+            failure.path, failure.error
+        );
+    }
+}
+
+fn assign_gamepads(
+    bindings: Res<InputBindings>,
+    mut slots: ResMut<GamepadSlots>,
+    gamepads: Query<Entity, With<Gamepad>>,
+) {
+    // Отключённый геймпад освобождает слот
+    slots.0.retain(|_, &mut pad| gamepads.contains(pad));
+
+    // Каждый кадр, а не только при подключении: слоты могут добавиться
+    // вместе с controls.cfg или освободиться позже
+    for gamepad in gamepads.iter() {
+        if slots.0.values().any(|&pad| pad == gamepad) {
+            continue;
+        }
+
+        let Some(slot) = (0..bindings.slots.len()).find(|slot| !slots.0.contains_key(slot)) else {
+            break;
+        };
+        slots.0.insert(slot, gamepad);
+    }
+}
+
+/// Movement action of a stick, one of four directions along the dominant axis.
+pub fn stick_action(stick: Vec2, dead_zone: f32) -> Option<Action> {
+    if stick.length() <= dead_zone {
+        return None;
+    }
+
+    LookDir::from_vec2(stick).map(Action::from_look_dir)
+}
+
+fn update_action_states(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    bindings: Res<InputBindings>,
+    slots: Res<GamepadSlots>,
+    virtual_actions: Res<VirtualActions>,
+    mut players: Query<(&InputSlot, &mut ActionState)>,
+) {
+    for (slot, mut state) in players.iter_mut() {
+        let gamepad = slots
+            .0
+            .get(&slot.0)
+            .and_then(|&entity| gamepads.get(entity).ok());
+
+        let mut held: HashSet<Action> = bindings
+            .slots
+            .get(slot.0)
+            .into_iter()
+            .flatten()
+            .filter(|(_, inputs)| {
+                inputs.iter().any(|input| match *input {
+                    Binding::Key(key) => keyboard.pressed(key),
+                    Binding::Pad(button) => gamepad.is_some_and(|pad| pad.pressed(button)),
+                })
+            })
+            .map(|(&action, _)| action)
+            .collect();
+
+        if let Some(action) =
+            gamepad.and_then(|pad| stick_action(pad.left_stick(), bindings.stick_dead_zone))
+        {
+            held.insert(action);
+        }
+
+        if slot.0 == 0 {
+            held.extend(virtual_actions.0.iter().copied());
+        }
+
+        state.update(held);
+    }
+}
+
+/// Keys that can be bound in `controls.cfg`.
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadEnter,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Backquote,
+    KeyCode::Minus,
+    KeyCode::Equal,
+];
+
+/// Buttons that can be bound in `controls.cfg`, written with a `Gamepad` prefix.
+const BINDABLE_BUTTONS: &[GamepadButton] = &[
+    GamepadButton::South,
+    GamepadButton::East,
+    GamepadButton::North,
+    GamepadButton::West,
+    GamepadButton::LeftTrigger,
+    GamepadButton::LeftTrigger2,
+    GamepadButton::RightTrigger,
+    GamepadButton::RightTrigger2,
+    GamepadButton::Select,
+    GamepadButton::Start,
+    GamepadButton::LeftThumb,
+    GamepadButton::RightThumb,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadRight,
+];
+
+fn parse_binding(name: &str) -> Option<Binding> {
+    if let Some(button) = name.strip_prefix("Gamepad") {
+        return BINDABLE_BUTTONS
+            .iter()
+            .copied()
+            .find(|candidate| format!("{candidate:?}") == button)
+            .map(Binding::Pad);
+    }
+
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|key| format!("{key:?}") == name)
+        .map(Binding::Key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_settings_and_player_sections() {
+        let bindings = InputBindings::parse(
+            "# comment\n\
+             move_policy = first_pressed\n\
+             stick_dead_zone = 0.5\n\
+             \n\
+             [player2]\n\
+             Fire = Space, GamepadSouth # inline comment\n\
+             MoveUp = ArrowUp\n",
+        )
+        .unwrap();
+
+        assert_eq!(bindings.move_policy, MovePolicy::FirstPressed);
+        assert_eq!(bindings.stick_dead_zone, 0.5);
+        assert_eq!(bindings.slots.len(), 2);
+        assert!(bindings.slots[0].is_empty());
+        assert_eq!(
+            bindings.slots[1][&Action::Fire],
+            vec![
+                Binding::Key(KeyCode::Space),
+                Binding::Pad(GamepadButton::South)
+            ]
+        );
+        assert_eq!(
+            bindings.slots[1][&Action::MoveUp],
+            vec![Binding::Key(KeyCode::ArrowUp)]
+        );
+    }
+
+    #[test]
+    fn default_file_parses() {
+        let text = include_str!("../../assets/config/controls.cfg");
+        let bindings = InputBindings::parse(text).unwrap();
+
+        assert_eq!(bindings.slots.len(), 1);
+        assert_eq!(bindings.slots[0].len(), Action::ALL.len());
+    }
+
+    #[test]
+    fn reports_the_broken_line() {
+        let unknown_key = InputBindings::parse("[player1]\nFire = KeyW\nMoveUp = Nope\n");
+        assert_eq!(unknown_key.unwrap_err(), "line 3: unknown key Nope");
+
+        let unknown_action = InputBindings::parse("[player1]\nJump = Space\n");
+        assert_eq!(unknown_action.unwrap_err(), "line 2: unknown action");
+
+        let bad_section = InputBindings::parse("[player0]\n");
+        assert_eq!(bad_section.unwrap_err(), "line 1: expected [playerN]");
+
+        let bad_setting = InputBindings::parse("move_policy = random\n[player1]\n");
+        assert_eq!(bad_setting.unwrap_err(), "line 1: unknown setting");
+
+        let bad_number = InputBindings::parse("stick_dead_zone = far\n[player1]\n");
+        assert_eq!(bad_number.unwrap_err(), "line 1: expected a number");
+
+        assert!(InputBindings::parse("move_policy = last_pressed\n").is_err());
+    }
+
+    #[test]
+    fn stick_ignores_dead_zone() {
+        assert_eq!(stick_action(Vec2::ZERO, 0.3), None);
+        assert_eq!(stick_action(Vec2::new(0.2, 0.2), 0.3), None);
+        assert_eq!(stick_action(Vec2::new(0.0, -0.3), 0.3), None);
+        assert_eq!(
+            stick_action(Vec2::new(0.0, -0.31), 0.3),
+            Some(Action::MoveDown)
+        );
+    }
+
+    #[test]
+    fn stick_picks_dominant_axis() {
+        assert_eq!(
+            stick_action(Vec2::new(0.9, 0.4), 0.3),
+            Some(Action::MoveRight)
+        );
+        assert_eq!(
+            stick_action(Vec2::new(-0.9, 0.4), 0.3),
+            Some(Action::MoveLeft)
+        );
+        assert_eq!(stick_action(Vec2::new(0.4, 0.9), 0.3), Some(Action::MoveUp));
+        assert_eq!(
+            stick_action(Vec2::new(-0.4, -0.9), 0.3),
+            Some(Action::MoveDown)
+        );
+        // На диагонали побеждает вертикаль
+        assert_eq!(stick_action(Vec2::new(0.7, 0.7), 0.3), Some(Action::MoveUp));
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::TiledMapAsset;
+use bevy_spritesheet_animation::prelude::{Animation, SpritesheetAnimation};
+
+use crate::{
+    game::{
+        actors::{
+            archetype::TankArchetype,
+            health::{DeathEvent, HealthSet},
+        },
+        state::InGame,
+        weapons::bullet::BulletIntercepted,
+    },
+    utils::{
+        pool::*,
+        tiled::{
+            destructor::{TileBlast, TileHit},
+            tileset_reader::{read_animation_handle_from_tileset, read_sprite_from_tileset},
+        },
+    },
+};
+
+const BOOM_TILESET: &str = "boom";
+const BOOM_BIG_TILESET: &str = "boom_big";
+const BOOM_TILE_ID: u32 = 0;
+const CRATER_TILE_ID: u32 = 3;
+
+/// Explosions are drawn over the tanks, craters right under them.
+const EXPLOSION_Z: f32 = -149.0;
+const CRATER_Z: f32 = -151.0;
+
+pub struct ExplosionPlugin;
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(PoolPlugin::<Explosion>::new(8, new_explosion))
+            .add_plugins(PoolPlugin::<Crater>::new(8, new_crater))
+            .add_message::<ExplosionEvent>()
+            .init_resource::<ExplosionAssets>()
+            .init_resource::<CraterSettings>()
+            .init_resource::<Craters>()
+            .add_systems(PreUpdate, load_explosion_assets)
+            .add_systems(OnExit(InGame), clear_effects)
+            .add_systems(
+                FixedPostUpdate,
+                (
+                    explosions_from_tile_hits,
+                    explosions_from_interceptions,
+                    explosions_from_tile_blasts,
+                    explosions_from_deaths
+                        .after(HealthSet::Damage)
+                        .before(HealthSet::Cleanup),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    spawn_explosions,
+                    explosion_lifetime_system,
+                    crater_lifetime_system,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplosionKind {
+    /// `boom` from `boom.tsx`, for bullet hits.
+    Small,
+    /// `boom_big` from `boom_big.tsx`, for destroyed tanks.
+    Big,
+}
+
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ExplosionEvent {
+    pub position: Vec2,
+    pub kind: ExplosionKind,
+    /// Leave a crater on the ground once the explosion is over.
+    pub crater: bool,
+}
+
+#[derive(Resource)]
+pub struct CraterSettings {
+    /// How long a crater stays on the ground, in seconds.
+    pub lifetime: f32,
+    /// The oldest crater is removed when there are more than this.
+    pub max_count: usize,
+}
+
+impl Default for CraterSettings {
+    fn default() -> Self {
+        Self {
+            lifetime: 30.0,
+            max_count: 32,
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+pub struct Explosion;
+
+#[derive(Component, Reflect)]
+pub struct Crater;
+
+#[derive(Component)]
+struct EffectLifetime(Timer);
+
+/// Active craters, oldest first.
+#[derive(Resource, Default)]
+struct Craters(VecDeque<Entity>);
+
+struct ExplosionAnimation {
+    sprite: Sprite,
+    animation: Handle<Animation>,
+    duration: Duration,
+}
+
+/// Sprites and animations read once from the tilesets and shared by every pooled effect.
+#[derive(Resource, Default)]
+struct ExplosionAssets {
+    small: Option<ExplosionAnimation>,
+    big: Option<ExplosionAnimation>,
+    crater: Option<Sprite>,
+}
+
+impl ExplosionAssets {
+    fn get(&self, kind: ExplosionKind) -> Option<&ExplosionAnimation> {
+        match kind {
+            ExplosionKind::Small => self.small.as_ref(),
+            ExplosionKind::Big => self.big.as_ref().or(self.small.as_ref()),
+        }
+    }
+}
+
+fn load_explosion_assets(
+    mut explosion_assets: ResMut<ExplosionAssets>,
+    tiled_map_assets: Res<Assets<TiledMapAsset>>,
+    mut animations: ResMut<Assets<Animation>>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    images: Res<Assets<Image>>,
+) {
+    let mut read_animation = |tileset: &str| {
+        read_animation_handle_from_tileset(
+            tileset.to_string(),
+            BOOM_TILE_ID,
+            &tiled_map_assets,
+            &mut animations,
+            &mut atlas_layouts,
+            &images,
+        )
+        .map(|(sprite, animation, duration)| ExplosionAnimation {
+            sprite,
+            animation,
+            duration,
+        })
+    };
+
+    if explosion_assets.small.is_none() {
+        explosion_assets.small = read_animation(BOOM_TILESET);
+    }
+    if explosion_assets.big.is_none() {
+        explosion_assets.big = read_animation(BOOM_BIG_TILESET);
+    }
+    if explosion_assets.crater.is_none() {
+        explosion_assets.crater = read_sprite_from_tileset(
+            BOOM_TILESET.to_string(),
+            CRATER_TILE_ID,
+            &tiled_map_assets,
+            &mut atlas_layouts,
+        );
+    }
+}
+
+fn explosions_from_tile_hits(
+    mut tile_hits: MessageReader<TileHit>,
+    mut explosions: MessageWriter<ExplosionEvent>,
+) {
+    for hit in tile_hits.read() {
+        explosions.write(ExplosionEvent {
+            position: hit.position,
+            kind: ExplosionKind::Small,
+            crater: false,
+        });
+    }
+}
+
+fn explosions_from_interceptions(
+    mut interceptions: MessageReader<BulletIntercepted>,
+    mut explosions: MessageWriter<ExplosionEvent>,
+) {
+    for interception in interceptions.read() {
+        explosions.write(ExplosionEvent {
+            position: interception.position,
+            kind: ExplosionKind::Small,
+            crater: false,
+        });
+    }
+}
+
+fn explosions_from_tile_blasts(
+    mut tile_blasts: MessageReader<TileBlast>,
+    mut explosions: MessageWriter<ExplosionEvent>,
+) {
+    for tile_blast in tile_blasts.read() {
+        explosions.write(ExplosionEvent {
+            position: tile_blast.position,
+            kind: ExplosionKind::Big,
+            crater: true,
+        });
+    }
+}
+
+fn explosions_from_deaths(
+    mut death_events: MessageReader<DeathEvent>,
+    tanks: Query<(), With<TankArchetype>>,
+    mut explosions: MessageWriter<ExplosionEvent>,
+) {
+    for event in death_events.read() {
+        let kind = if tanks.contains(event.entity) {
+            ExplosionKind::Big
+        } else {
+            ExplosionKind::Small
+        };
+
+        explosions.write(ExplosionEvent {
+            position: event.position,
+            kind,
+            crater: true,
+        });
+    }
+}
+
+fn spawn_explosions(
+    mut commands: Commands,
+    mut explosions: MessageReader<ExplosionEvent>,
+    explosion_assets: Res<ExplosionAssets>,
+    settings: Res<CraterSettings>,
+    mut explosion_pool: ResMut<Pool<Explosion>>,
+    mut crater_pool: ResMut<Pool<Crater>>,
+    mut craters: ResMut<Craters>,
+) {
+    for event in explosions.read() {
+        if let Some(explosion) = explosion_assets.get(event.kind) {
+            activate_from_pool(&mut commands, &mut explosion_pool, |entity, commands| {
+                commands.entity(entity).insert((
+                    Transform::from_translation(event.position.extend(EXPLOSION_Z)),
+                    explosion.sprite.clone(),
+                    SpritesheetAnimation::new(explosion.animation.clone()),
+                    EffectLifetime(Timer::new(explosion.duration, TimerMode::Once)),
+                ));
+            });
+        }
+
+        if !event.crater {
+            continue;
+        }
+
+        let Some(crater_sprite) = &explosion_assets.crater else {
+            continue;
+        };
+
+        if craters.0.len() >= settings.max_count
+            && let Some(oldest) = craters.0.pop_front()
+        {
+            deactivate_to_pool(
+                &mut commands,
+                &mut crater_pool,
+                oldest,
+                |entity, commands| {
+                    commands.entity(entity).remove::<EffectLifetime>();
+                },
+            );
+        }
+
+        activate_from_pool(&mut commands, &mut crater_pool, |entity, commands| {
+            commands.entity(entity).insert((
+                Transform::from_translation(event.position.extend(CRATER_Z)),
+                crater_sprite.clone(),
+                EffectLifetime(Timer::from_seconds(settings.lifetime, TimerMode::Once)),
+            ));
+            craters.0.push_back(entity);
+        });
+    }
+}
+
+fn explosion_lifetime_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut explosions: Query<(Entity, &mut EffectLifetime), With<Active<Explosion>>>,
+    mut pool: ResMut<Pool<Explosion>>,
+) {
+    for (entity, mut lifetime) in explosions.iter_mut() {
+        if lifetime.0.tick(time.delta()).is_finished() {
+            deactivate_to_pool(&mut commands, &mut pool, entity, |entity, commands| {
+                commands
+                    .entity(entity)
+                    .remove::<(EffectLifetime, SpritesheetAnimation)>();
+            });
+        }
+    }
+}
+
+fn crater_lifetime_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut active_craters: Query<(Entity, &mut EffectLifetime), With<Active<Crater>>>,
+    mut pool: ResMut<Pool<Crater>>,
+    mut craters: ResMut<Craters>,
+) {
+    for (entity, mut lifetime) in active_craters.iter_mut() {
+        if lifetime.0.tick(time.delta()).is_finished() {
+            craters.0.retain(|&crater| crater != entity);
+            deactivate_to_pool(&mut commands, &mut pool, entity, |entity, commands| {
+                commands.entity(entity).remove::<EffectLifetime>();
+            });
+        }
+    }
+}
+
+/// Hides everything left over from the level.
+fn clear_effects(
+    mut commands: Commands,
+    explosions: Query<Entity, With<Active<Explosion>>>,
+    mut explosion_pool: ResMut<Pool<Explosion>>,
+    mut crater_pool: ResMut<Pool<Crater>>,
+    mut craters: ResMut<Craters>,
+) {
+    for entity in explosions.iter() {
+        deactivate_to_pool(
+            &mut commands,
+            &mut explosion_pool,
+            entity,
+            |entity, commands| {
+                commands
+                    .entity(entity)
+                    .remove::<(EffectLifetime, SpritesheetAnimation)>();
+            },
+        );
+    }
+
+    for entity in craters.0.drain(..) {
+        deactivate_to_pool(
+            &mut commands,
+            &mut crater_pool,
+            entity,
+            |entity, commands| {
+                commands.entity(entity).remove::<EffectLifetime>();
+            },
+        );
+    }
+}
+
+fn new_explosion(commands: &mut Commands) -> Entity {
+    commands
+        .spawn((Explosion, Sprite::default(), Visibility::Hidden))
+        .id()
+}
+
+fn new_crater(commands: &mut Commands) -> Entity {
+    commands
+        .spawn((Crater, Sprite::default(), Visibility::Hidden))
+        .id()
+}
@@ -0,0 +1,14 @@
+use bevy::prelude::*;
+
+use crate::game::effects::{explosion::ExplosionPlugin, particles::ParticlePlugin};
+
+pub mod explosion;
+pub mod particles;
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((ExplosionPlugin, ParticlePlugin));
+    }
+}
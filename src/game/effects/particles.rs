@@ -0,0 +1,510 @@
+use bevy::{color::Mix, prelude::*};
+use rand::Rng;
+
+use crate::{
+    game::{
+        actors::wreck::Wreck,
+        state::{GameState, InGame},
+        weapons::{
+            bullet::{PLAYER_SIZE, bullet_spawn_offset},
+            weapon::ShotFired,
+        },
+    },
+    utils::{pool::*, tiled::destructor::TileHit},
+};
+
+/// Particles are drawn over the explosions.
+const PARTICLE_Z: f32 = -148.0;
+
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(PoolPlugin::<Particle>::new(64, new_particle))
+            .add_message::<EmitParticles>()
+            .init_resource::<ParticleSettings>()
+            .init_resource::<ActiveParticles>()
+            .add_systems(OnExit(InGame), clear_particles)
+            .add_systems(
+                Update,
+                (
+                    particles_from_shots,
+                    particles_from_tile_hits,
+                    attach_wreck_smoke,
+                    emit_wreck_smoke,
+                    spawn_particles,
+                    update_particles,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParticleKind {
+    /// Short flash in front of the barrel.
+    MuzzleFlash,
+    /// Bright sparks where a projectile hits a tile.
+    Sparks,
+    /// Pieces of a destroyed tile.
+    Debris,
+    /// Slow smoke rising from wrecks.
+    Smoke,
+}
+
+/// Emits a burst of `kind` particles at `position`. A zero `direction` scatters them all around.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct EmitParticles {
+    pub position: Vec2,
+    pub direction: Vec2,
+    pub kind: ParticleKind,
+}
+
+/// How one burst of a [`ParticleKind`] looks. Ranges are `(min, max)`.
+#[derive(Debug, Clone)]
+pub struct BurstSettings {
+    pub count: u32,
+    /// Width of the cone around the burst direction, in degrees.
+    pub cone: f32,
+    pub speed: (f32, f32),
+    /// In seconds.
+    pub lifetime: (f32, f32),
+    /// Fraction of the velocity lost per second.
+    pub drag: f32,
+    pub start_size: f32,
+    pub end_size: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+}
+
+impl BurstSettings {
+    /// Rolls the initial state of every particle of one burst.
+    pub fn roll(&self, direction: Vec2, rng: &mut impl Rng) -> Vec<ParticleState> {
+        let half_cone = self.cone.to_radians() / 2.0;
+
+        (0..self.count)
+            .map(|_| {
+                let base = direction.try_normalize().unwrap_or_else(|| {
+                    Vec2::from_angle(rng.random_range(0.0..std::f32::consts::TAU))
+                });
+                let angle = if half_cone > 0.0 {
+                    rng.random_range(-half_cone..=half_cone)
+                } else {
+                    0.0
+                };
+                let speed = rng.random_range(self.speed.0..=self.speed.1);
+
+                ParticleState {
+                    velocity: Vec2::from_angle(angle).rotate(base) * speed,
+                    drag: self.drag,
+                    age: 0.0,
+                    lifetime: rng.random_range(self.lifetime.0..=self.lifetime.1),
+                    start_size: self.start_size,
+                    end_size: self.end_size,
+                    start_color: self.start_color,
+                    end_color: self.end_color,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct ParticleSettings {
+    /// New particles are dropped while this many are alive.
+    pub max_particles: usize,
+    /// Seconds between two puffs of wreck smoke.
+    pub smoke_interval: f32,
+    /// How long a wreck keeps smoking, in seconds.
+    pub smoke_duration: f32,
+    pub muzzle_flash: BurstSettings,
+    pub sparks: BurstSettings,
+    pub debris: BurstSettings,
+    pub smoke: BurstSettings,
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        Self {
+            max_particles: 512,
+            smoke_interval: 0.25,
+            smoke_duration: 10.0,
+            muzzle_flash: BurstSettings {
+                count: 6,
+                cone: 30.0,
+                speed: (40.0, 90.0),
+                lifetime: (0.05, 0.12),
+                drag: 8.0,
+                start_size: 3.0,
+                end_size: 1.0,
+                start_color: Color::srgb(1.0, 0.9, 0.5),
+                end_color: Color::srgba(1.0, 0.4, 0.1, 0.0),
+            },
+            sparks: BurstSettings {
+                count: 8,
+                cone: 360.0,
+                speed: (60.0, 140.0),
+                lifetime: (0.15, 0.35),
+                drag: 4.0,
+                start_size: 1.5,
+                end_size: 0.5,
+                start_color: Color::srgb(1.0, 0.8, 0.3),
+                end_color: Color::srgba(0.9, 0.3, 0.1, 0.0),
+            },
+            debris: BurstSettings {
+                count: 6,
+                cone: 360.0,
+                speed: (20.0, 60.0),
+                lifetime: (0.4, 0.8),
+                drag: 3.0,
+                start_size: 2.0,
+                end_size: 1.5,
+                start_color: Color::srgb(0.55, 0.3, 0.2),
+                end_color: Color::srgba(0.4, 0.25, 0.15, 0.0),
+            },
+            smoke: BurstSettings {
+                count: 1,
+                cone: 40.0,
+                speed: (6.0, 14.0),
+                lifetime: (1.2, 2.0),
+                drag: 0.5,
+                start_size: 3.0,
+                end_size: 8.0,
+                start_color: Color::srgba(0.3, 0.3, 0.3, 0.6),
+                end_color: Color::srgba(0.5, 0.5, 0.5, 0.0),
+            },
+        }
+    }
+}
+
+impl ParticleSettings {
+    pub fn burst(&self, kind: ParticleKind) -> &BurstSettings {
+        match kind {
+            ParticleKind::MuzzleFlash => &self.muzzle_flash,
+            ParticleKind::Sparks => &self.sparks,
+            ParticleKind::Debris => &self.debris,
+            ParticleKind::Smoke => &self.smoke,
+        }
+    }
+}
+
+/// Number of particles taken from the pool and not returned yet.
+#[derive(Resource, Default, Debug)]
+pub struct ActiveParticles(pub usize);
+
+#[derive(Component, Reflect)]
+pub struct Particle;
+
+/// Simulation state of a particle. Updated on the CPU, the sprite only mirrors it.
+#[derive(Component, Clone, Debug)]
+pub struct ParticleState {
+    pub velocity: Vec2,
+    pub drag: f32,
+    pub age: f32,
+    pub lifetime: f32,
+    pub start_size: f32,
+    pub end_size: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+}
+
+impl ParticleState {
+    /// Ages the particle by `dt` seconds and returns how far it moved.
+    pub fn advance(&mut self, dt: f32) -> Vec2 {
+        self.age += dt;
+        let offset = self.velocity * dt;
+        self.velocity *= (1.0 - self.drag * dt).max(0.0);
+        offset
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    /// From 0 when emitted to 1 at the end of its life.
+    pub fn progress(&self) -> f32 {
+        (self.age / self.lifetime.max(f32::EPSILON)).clamp(0.0, 1.0)
+    }
+
+    pub fn size(&self) -> f32 {
+        self.start_size.lerp(self.end_size, self.progress())
+    }
+
+    pub fn color(&self) -> Color {
+        self.start_color.mix(&self.end_color, self.progress())
+    }
+}
+
+/// Puffs of smoke over a fresh wreck.
+#[derive(Component)]
+struct WreckSmoke {
+    interval: Timer,
+    remaining: Timer,
+}
+
+fn particles_from_shots(
+    mut shots: MessageReader<ShotFired>,
+    mut particles: MessageWriter<EmitParticles>,
+) {
+    for shot in shots.read() {
+        let muzzle = shot.position + bullet_spawn_offset(shot.direction, PLAYER_SIZE).truncate();
+
+        particles.write(EmitParticles {
+            position: muzzle,
+            direction: shot.direction,
+            kind: ParticleKind::MuzzleFlash,
+        });
+    }
+}
+
+fn particles_from_tile_hits(
+    mut tile_hits: MessageReader<TileHit>,
+    mut particles: MessageWriter<EmitParticles>,
+) {
+    for hit in tile_hits.read() {
+        particles.write(EmitParticles {
+            position: hit.position,
+            direction: Vec2::ZERO,
+            kind: ParticleKind::Sparks,
+        });
+
+        if hit.destroyed {
+            particles.write(EmitParticles {
+                position: hit.position,
+                direction: Vec2::ZERO,
+                kind: ParticleKind::Debris,
+            });
+        }
+    }
+}
+
+fn attach_wreck_smoke(
+    mut commands: Commands,
+    wrecks: Query<Entity, Added<Wreck>>,
+    settings: Res<ParticleSettings>,
+) {
+    for entity in wrecks.iter() {
+        commands.entity(entity).insert(WreckSmoke {
+            interval: Timer::from_seconds(settings.smoke_interval, TimerMode::Repeating),
+            remaining: Timer::from_seconds(settings.smoke_duration, TimerMode::Once),
+        });
+    }
+}
+
+fn emit_wreck_smoke(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut wrecks: Query<(Entity, &GlobalTransform, &mut WreckSmoke)>,
+    mut particles: MessageWriter<EmitParticles>,
+) {
+    for (entity, transform, mut smoke) in wrecks.iter_mut() {
+        if smoke.remaining.tick(time.delta()).is_finished() {
+            commands.entity(entity).remove::<WreckSmoke>();
+            continue;
+        }
+
+        for _ in 0..smoke.interval.tick(time.delta()).times_finished_this_tick() {
+            particles.write(EmitParticles {
+                position: transform.translation().truncate(),
+                direction: Vec2::Y,
+                kind: ParticleKind::Smoke,
+            });
+        }
+    }
+}
+
+fn spawn_particles(
+    mut commands: Commands,
+    mut requests: MessageReader<EmitParticles>,
+    settings: Res<ParticleSettings>,
+    mut active: ResMut<ActiveParticles>,
+    mut pool: ResMut<Pool<Particle>>,
+) {
+    let mut rng = rand::rng();
+
+    for request in requests.read() {
+        // Лишние частицы отбрасываем, но запросы дочитываем, чтобы они не копились
+        let budget = settings.max_particles.saturating_sub(active.0);
+        if budget == 0 {
+            continue;
+        }
+
+        for state in settings
+            .burst(request.kind)
+            .roll(request.direction, &mut rng)
+            .into_iter()
+            .take(budget)
+        {
+            activate_from_pool(&mut commands, &mut pool, |entity, commands| {
+                commands.entity(entity).insert((
+                    Transform::from_translation(request.position.extend(PARTICLE_Z)),
+                    Sprite {
+                        color: state.color(),
+                        custom_size: Some(Vec2::splat(state.size())),
+                        ..default()
+                    },
+                    state,
+                ));
+            });
+            active.0 += 1;
+        }
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<
+        (Entity, &mut ParticleState, &mut Transform, &mut Sprite),
+        With<Active<Particle>>,
+    >,
+    mut active: ResMut<ActiveParticles>,
+    mut pool: ResMut<Pool<Particle>>,
+) {
+    let dt = time.delta_secs();
+
+    for (entity, mut state, mut transform, mut sprite) in particles.iter_mut() {
+        let offset = state.advance(dt);
+
+        if !state.is_alive() {
+            particle_deactivate(&mut commands, &mut pool, entity);
+            active.0 = active.0.saturating_sub(1);
+            continue;
+        }
+
+        transform.translation += offset.extend(0.0);
+        sprite.color = state.color();
+        sprite.custom_size = Some(Vec2::splat(state.size()));
+    }
+}
+
+fn clear_particles(
+    mut commands: Commands,
+    particles: Query<Entity, With<Active<Particle>>>,
+    mut active: ResMut<ActiveParticles>,
+    mut pool: ResMut<Pool<Particle>>,
+) {
+    for entity in particles.iter() {
+        particle_deactivate(&mut commands, &mut pool, entity);
+    }
+    active.0 = 0;
+}
+
+fn particle_deactivate(commands: &mut Commands, pool: &mut Pool<Particle>, particle: Entity) {
+    deactivate_to_pool(commands, pool, particle, |entity, commands| {
+        commands.entity(entity).remove::<ParticleState>();
+    });
+}
+
+fn new_particle(commands: &mut Commands) -> Entity {
+    commands
+        .spawn((Particle, Sprite::default(), Visibility::Hidden))
+        .id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn particle(lifetime: f32) -> ParticleState {
+        ParticleState {
+            velocity: Vec2::new(10.0, 0.0),
+            drag: 0.5,
+            age: 0.0,
+            lifetime,
+            start_size: 2.0,
+            end_size: 4.0,
+            start_color: Color::WHITE,
+            end_color: Color::BLACK,
+        }
+    }
+
+    #[test]
+    fn advance_moves_and_slows_down() {
+        let mut state = particle(1.0);
+
+        let offset = state.advance(0.5);
+
+        assert_eq!(offset, Vec2::new(5.0, 0.0));
+        assert_eq!(state.velocity, Vec2::new(7.5, 0.0));
+        assert_eq!(state.age, 0.5);
+    }
+
+    #[test]
+    fn dies_at_the_end_of_its_lifetime() {
+        let mut state = particle(1.0);
+
+        state.advance(0.75);
+        assert!(state.is_alive());
+        assert_eq!(state.progress(), 0.75);
+        assert_eq!(state.size(), 3.5);
+
+        state.advance(0.25);
+        assert!(!state.is_alive());
+        assert_eq!(state.progress(), 1.0);
+        assert_eq!(state.size(), 4.0);
+
+        // За концом жизни прогресс не растёт
+        state.advance(1.0);
+        assert_eq!(state.progress(), 1.0);
+    }
+
+    #[test]
+    fn zero_lifetime_is_dead_at_once() {
+        let state = particle(0.0);
+
+        assert!(!state.is_alive());
+        assert_eq!(state.progress(), 0.0);
+    }
+
+    #[test]
+    fn burst_rolls_count_particles_inside_ranges() {
+        let settings = ParticleSettings::default();
+        let burst = settings.burst(ParticleKind::MuzzleFlash);
+
+        let states = burst.roll(Vec2::X, &mut rand::rng());
+
+        assert_eq!(states.len(), burst.count as usize);
+        for state in &states {
+            let speed = state.velocity.length();
+            assert!(speed >= burst.speed.0 - 1e-3 && speed <= burst.speed.1 + 1e-3);
+            assert!(state.lifetime >= burst.lifetime.0 && state.lifetime <= burst.lifetime.1);
+            let angle = Vec2::X.angle_to(state.velocity).to_degrees().abs();
+            assert!(angle <= burst.cone / 2.0 + 1e-3);
+        }
+    }
+
+    #[test]
+    fn spawning_stops_at_max_particles() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, PoolPlugin::<Particle>::new(4, new_particle)))
+            .add_message::<EmitParticles>()
+            .insert_resource(ParticleSettings {
+                max_particles: 10,
+                ..default()
+            })
+            .init_resource::<ActiveParticles>()
+            .add_systems(Update, spawn_particles);
+
+        for _ in 0..3 {
+            app.world_mut().write_message(EmitParticles {
+                position: Vec2::ZERO,
+                direction: Vec2::ZERO,
+                kind: ParticleKind::Sparks,
+            });
+        }
+        app.update();
+
+        let mut spawned = app
+            .world_mut()
+            .query_filtered::<(), With<Active<Particle>>>();
+        assert_eq!(app.world().resource::<ActiveParticles>().0, 10);
+        assert_eq!(spawned.iter(app.world()).count(), 10);
+
+        // Отброшенные запросы не переносятся на следующий кадр
+        app.world_mut().resource_mut::<ActiveParticles>().0 = 0;
+        app.update();
+        assert_eq!(app.world().resource::<ActiveParticles>().0, 0);
+    }
+}
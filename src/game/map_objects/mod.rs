@@ -1,10 +1,11 @@
 use bevy::prelude::*;
 
 use crate::{
-    game::map_objects::spawn::Spawn,
+    game::map_objects::{pickup::Pickup, spawn::Spawn},
     utils::tiled::map_object_type::{MapObjectTypePlugin, MapObjectsTypeRegistry},
 };
 
+pub mod pickup;
 pub mod spawn;
 
 pub struct MapObjectsPlugin;
@@ -12,7 +13,9 @@ pub struct MapObjectsPlugin;
 impl Plugin for MapObjectsPlugin {
     fn build(&self, app: &mut App) {
         let mut map_objects_type_registry = MapObjectsTypeRegistry::default();
-        map_objects_type_registry.register::<Spawn>();
+        map_objects_type_registry
+            .register::<Spawn>()
+            .register::<Pickup>();
 
         app.insert_resource(map_objects_type_registry);
         app.add_plugins(MapObjectTypePlugin);
@@ -0,0 +1,58 @@
+use avian2d::prelude::{RigidBody, Sensor};
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::{
+    ObjectCreated, TiledEvent, TiledMapAsset,
+    tiled::{Layer, Object},
+};
+
+use crate::{
+    game::weapons::ammo::AmmoPickup,
+    utils::tiled::{
+        map_object_type::MapObjectType, map_tile_type::MapItemBasic,
+        tileset_reader::read_f32_property,
+    },
+};
+
+const DEFAULT_AMMO_AMOUNT: u32 = 10;
+
+#[derive(Component, Reflect)]
+pub struct Pickup;
+
+impl MapItemBasic for Pickup {
+    fn layer_class() -> String {
+        "pickups".to_string()
+    }
+
+    fn class() -> String {
+        "pickup".to_string()
+    }
+
+    fn on_collision_created(
+        entity_commands: &mut EntityCommands,
+        _assets: &Res<Assets<TiledMapAsset>>,
+        _data: &Layer,
+    ) {
+        entity_commands
+            .insert(RigidBody::Static)
+            .insert(Sensor)
+            .insert(Pickup);
+    }
+}
+
+impl MapObjectType for Pickup {
+    fn on_object_created(
+        commands: &mut Commands,
+        _assets: &Res<Assets<TiledMapAsset>>,
+        object_created: &On<TiledEvent<ObjectCreated>>,
+        object: &Object,
+    ) {
+        if object.user_type == "pickup_ammo" {
+            let amount = read_f32_property(&object.properties, "amount")
+                .map_or(DEFAULT_AMMO_AMOUNT, |amount| amount as u32);
+
+            commands
+                .entity(object_created.event().origin)
+                .insert((Pickup, AmmoPickup { amount }));
+        }
+    }
+}
@@ -13,12 +13,16 @@ use bevy::{
 };
 use bevy_ecs_tiled::prelude::{
     ObjectCreated, TileCreated, TiledEvent, TiledMapAsset, TiledObject,
-    tiled::{Layer, Object},
+    tiled::{Layer, Object, Properties},
 };
 
 use crate::{
-    game::GameLayer,
-    utils::tiled::{map_object_type::MapObjectType, map_tile_type::MapItemBasic},
+    game::{GameLayer, actors::enemy::DEFAULT_ENEMY_ARCHETYPE},
+    utils::tiled::{
+        map_object_type::MapObjectType,
+        map_tile_type::MapItemBasic,
+        tileset_reader::{read_f32_property, read_string_property},
+    },
 };
 
 #[derive(Component, Reflect)]
@@ -27,6 +31,35 @@ pub struct Spawn;
 #[derive(Component)]
 pub struct PlayerSpawn;
 
+/// Enemy spawn point configured by the object properties in Tiled.
+#[derive(Component)]
+pub struct EnemySpawn {
+    pub archetype: String,
+    /// How many enemies to spawn per wave.
+    pub count: u32,
+    /// Delay between two spawned enemies, in seconds.
+    pub interval: f32,
+    /// Wave the spawn point takes part in, 0 means every wave.
+    pub wave: u32,
+}
+
+impl EnemySpawn {
+    pub fn from_properties(properties: &Properties) -> Self {
+        Self {
+            archetype: read_string_property(properties, "archetype")
+                .unwrap_or(DEFAULT_ENEMY_ARCHETYPE)
+                .to_string(),
+            count: read_f32_property(properties, "count").map_or(1, |count| count as u32),
+            interval: read_f32_property(properties, "interval").map_or(2.0, |ms| ms / 1000.0),
+            wave: read_f32_property(properties, "wave").map_or(0, |wave| wave as u32),
+        }
+    }
+
+    pub fn is_active_in(&self, wave: u32) -> bool {
+        self.wave == 0 || self.wave == wave
+    }
+}
+
 impl MapItemBasic for Spawn {
     fn layer_class() -> String {
         "spawn".to_string()
@@ -59,6 +92,11 @@ impl MapObjectType for Spawn {
         if object.user_type == "spawn_player" {
             let object_entity = object_created.event().origin;
             commands.entity(object_entity).insert(PlayerSpawn);
+        } else if object.user_type == "spawn_enemy" {
+            let object_entity = object_created.event().origin;
+            commands
+                .entity(object_entity)
+                .insert(EnemySpawn::from_properties(&object.properties));
         }
     }
 }
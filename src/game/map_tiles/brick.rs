@@ -1,18 +1,23 @@
 use avian2d::prelude::{CollisionLayers, RigidBody};
 use bevy::prelude::*;
-use bevy_ecs_tiled::prelude::{TiledEvent, TiledMapAsset, tiled::Layer};
+use bevy_ecs_tiled::prelude::{TilePos, TiledEvent, TiledMapAsset, tiled::Layer};
 
 use crate::{
     game::GameLayer,
     utils::tiled::{
-        destructor::AffectedByDestructor,
+        destructor::{AffectedByDestructor, TileHealthStorage},
         map_tile_type::{MapItemBasic, MapTileType},
+        tileset_reader::{read_bool_property, read_f32_property},
     },
 };
 
 #[derive(Component, Reflect)]
 pub struct Brick;
 
+/// Armored brick, same as [`Brick`] but usually with a lot more `brickHealth`.
+#[derive(Component, Reflect)]
+pub struct HeavyBrick;
+
 impl MapItemBasic for Brick {
     fn on_collision_created(
         entity_commands: &mut EntityCommands,
@@ -22,7 +27,10 @@ impl MapItemBasic for Brick {
         entity_commands
             .insert(RigidBody::Static)
             .insert(Brick)
-            .insert(CollisionLayers::new(GameLayer::Bricks, [GameLayer::Player]));
+            .insert(CollisionLayers::new(
+                GameLayer::Bricks,
+                [GameLayer::Player, GameLayer::Projectile],
+            ));
     }
 
     fn class() -> String {
@@ -37,21 +45,102 @@ impl MapItemBasic for Brick {
 impl MapTileType for Brick {
     fn on_tile_created(
         commands: &mut Commands,
-        _assets: &Res<Assets<TiledMapAsset>>,
+        assets: &Res<Assets<TiledMapAsset>>,
         tile_created: &On<TiledEvent<bevy_ecs_tiled::prelude::TileCreated>>,
     ) {
-        match tile_created.event().get_tile_entity() {
-            None => {}
-            Some(entity) => {
-                commands.entity(entity).insert(Brick);
-                if let Some(tilemap_entity) = tile_created.event().get_tilemap_entity() {
-                    commands
-                        .entity(tilemap_entity)
-                        .insert(AffectedByDestructor {
-                            layer_id: tile_created.get_layer_index().unwrap(),
-                        });
-                }
-            }
+        if let Some(entity) = tile_created.event().get_tile_entity() {
+            commands.entity(entity).insert(Brick);
+            register_destructible_tile(commands, assets, tile_created);
+        }
+    }
+}
+
+impl MapItemBasic for HeavyBrick {
+    fn on_collision_created(
+        entity_commands: &mut EntityCommands,
+        assets: &Res<Assets<TiledMapAsset>>,
+        data: &Layer,
+    ) {
+        Brick::on_collision_created(entity_commands, assets, data);
+    }
+
+    fn class() -> String {
+        "heavy_brick".to_string()
+    }
+
+    fn layer_class() -> String {
+        Brick::layer_class()
+    }
+}
+
+impl MapTileType for HeavyBrick {
+    fn on_tile_created(
+        commands: &mut Commands,
+        assets: &Res<Assets<TiledMapAsset>>,
+        tile_created: &On<TiledEvent<bevy_ecs_tiled::prelude::TileCreated>>,
+    ) {
+        if let Some(entity) = tile_created.event().get_tile_entity() {
+            commands.entity(entity).insert((Brick, HeavyBrick));
+            register_destructible_tile(commands, assets, tile_created);
         }
     }
 }
+
+/// Tiles with at least this much `brickHealth` count as armored even without an `armor` property.
+const ARMORED_HEALTH: f32 = 1000.0;
+/// Armor of tiles with `steel = true`.
+const STEEL_ARMOR: u32 = 2;
+
+/// Marks the tile layer as destructible and stores the tile `brickHealth` and armor for its position.
+fn register_destructible_tile(
+    commands: &mut Commands,
+    assets: &Res<Assets<TiledMapAsset>>,
+    tile_created: &On<TiledEvent<bevy_ecs_tiled::prelude::TileCreated>>,
+) {
+    let (Some(tile_entity), Some(tilemap_entity)) = (
+        tile_created.event().get_tile_entity(),
+        tile_created.event().get_tilemap_entity(),
+    ) else {
+        return;
+    };
+
+    commands
+        .entity(tilemap_entity)
+        .insert(AffectedByDestructor {
+            layer_id: tile_created.get_layer_index().unwrap(),
+        })
+        .entry::<TileHealthStorage>()
+        .or_default();
+
+    let Some(tile) = tile_created.event().get_tile(assets) else {
+        return;
+    };
+
+    let health = read_f32_property(&tile.properties, "brickHealth");
+    let armor = read_f32_property(&tile.properties, "armor")
+        .map(|armor| armor as u32)
+        .or_else(|| {
+            read_bool_property(&tile.properties, "steel")
+                .unwrap_or(false)
+                .then_some(STEEL_ARMOR)
+        })
+        .or_else(|| health.filter(|&health| health >= ARMORED_HEALTH).map(|_| 1));
+
+    if health.is_none() && armor.is_none() {
+        return;
+    }
+
+    commands.queue(move |world: &mut World| {
+        let Some(&pos) = world.get::<TilePos>(tile_entity) else {
+            return;
+        };
+        if let Some(mut storage) = world.get_mut::<TileHealthStorage>(tilemap_entity) {
+            if let Some(health) = health {
+                storage.set(pos, health);
+            }
+            if let Some(armor) = armor {
+                storage.set_armor(pos, armor);
+            }
+        }
+    });
+}
@@ -1,7 +1,11 @@
 use bevy::prelude::*;
 
 use crate::{
-    game::map_tiles::{brick::Brick, tree::Tree, water::Water},
+    game::map_tiles::{
+        brick::{Brick, HeavyBrick},
+        tree::Tree,
+        water::Water,
+    },
     utils::tiled::map_tile_type::{MapTileTypePlugin, MapTileTypeRegistry},
 };
 
@@ -17,6 +21,7 @@ impl Plugin for MapTilesPlugin {
         map_tile_type_registry
             .register::<Tree>()
             .register::<Brick>()
+            .register::<HeavyBrick>()
             .register::<Water>();
 
         app.insert_resource(map_tile_type_registry);
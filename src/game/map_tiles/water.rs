@@ -40,5 +40,8 @@ impl MapTileType for Water {
         assets: &Res<Assets<TiledMapAsset>>,
         tile_created: &On<TiledEvent<bevy_ecs_tiled::prelude::TileCreated>>,
     ) {
+        if let Some(entity) = tile_created.event().get_tile_entity() {
+            commands.entity(entity).insert(Water);
+        }
     }
 }
@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+
+use crate::game::state::GameState;
+
+const FONT_PATH: &str = "fonts/kongtext.ttf";
+
+const BUTTON_COLOR: Color = Color::srgba(0.1, 0.1, 0.1, 0.8);
+const BUTTON_HOVER_COLOR: Color = Color::srgba(0.3, 0.3, 0.3, 0.9);
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(OnEnter(GameState::Paused), spawn_pause_menu)
+            .add_systems(OnEnter(GameState::GameOver), spawn_game_over_menu)
+            .add_systems(
+                Update,
+                (
+                    menu_button_system,
+                    start_on_enter.run_if(in_state(GameState::MainMenu)),
+                ),
+            );
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuButton {
+    Play,
+    Resume,
+    MainMenu,
+    Quit,
+}
+
+impl MenuButton {
+    fn label(self) -> &'static str {
+        match self {
+            MenuButton::Play => "Play",
+            MenuButton::Resume => "Resume",
+            MenuButton::MainMenu => "Main menu",
+            MenuButton::Quit => "Quit",
+        }
+    }
+}
+
+fn spawn_main_menu(commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        commands,
+        &asset_server,
+        GameState::MainMenu,
+        "TANKS",
+        Some("WASD - move, Space - fire, Esc - pause"),
+        &[MenuButton::Play, MenuButton::Quit],
+    );
+}
+
+fn spawn_pause_menu(commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        commands,
+        &asset_server,
+        GameState::Paused,
+        "Paused",
+        None,
+        &[MenuButton::Resume, MenuButton::MainMenu],
+    );
+}
+
+fn spawn_game_over_menu(commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        commands,
+        &asset_server,
+        GameState::GameOver,
+        "Game over",
+        None,
+        &[MenuButton::MainMenu],
+    );
+}
+
+/// Centered column with a title and buttons, removed when `state` is left.
+fn spawn_menu(
+    mut commands: Commands,
+    asset_server: &AssetServer,
+    state: GameState,
+    title: &str,
+    hint: Option<&str>,
+    buttons: &[MenuButton],
+) {
+    let font = asset_server.load(FONT_PATH);
+
+    commands
+        .spawn((
+            Node {
+                width: percent(100),
+                height: percent(100),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: px(12),
+                ..default()
+            },
+            DespawnOnExit(state),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(title),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+
+            for &button in buttons {
+                parent
+                    .spawn((
+                        Button,
+                        button,
+                        Node {
+                            width: px(240),
+                            padding: UiRect::all(px(10)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        BackgroundColor(BUTTON_COLOR),
+                    ))
+                    .with_child((
+                        Text::new(button.label()),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 20.0,
+                            ..default()
+                        },
+                    ));
+            }
+
+            if let Some(hint) = hint {
+                parent.spawn((
+                    Text::new(hint),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 12.0,
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+fn menu_button_system(
+    mut buttons: Query<
+        (&Interaction, &MenuButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut app_exit: MessageWriter<AppExit>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match interaction {
+            Interaction::Pressed => match button {
+                MenuButton::Play | MenuButton::Resume => next_state.set(GameState::Playing),
+                MenuButton::MainMenu => next_state.set(GameState::MainMenu),
+                MenuButton::Quit => {
+                    app_exit.write(AppExit::Success);
+                }
+            },
+            Interaction::Hovered => color.0 = BUTTON_HOVER_COLOR,
+            Interaction::None => color.0 = BUTTON_COLOR,
+        }
+    }
+}
+
+fn start_on_enter(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Enter) {
+        next_state.set(GameState::Playing);
+    }
+}
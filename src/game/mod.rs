@@ -1,8 +1,16 @@
 use avian2d::prelude::PhysicsLayer;
 
 pub mod actors;
+pub mod audio;
+pub mod collision_hooks;
+pub mod controls;
+pub mod effects;
 pub mod map_objects;
 pub mod map_tiles;
+pub mod menu;
+pub mod state;
+pub mod virtual_joystick;
+pub mod waves;
 pub mod weapons;
 
 #[derive(PhysicsLayer, Default)]
@@ -14,4 +22,7 @@ pub enum GameLayer {
     Bricks,
     Trees,
     Sky,
+    Projectile,
+    /// Mines and charges lying on the ground, only tanks touch them.
+    Charge,
 }
@@ -0,0 +1,226 @@
+use bevy::{prelude::*, transform::TransformSystems};
+use bevy_ecs_tiled::prelude::*;
+
+use crate::{
+    MAP_CHUNK_SIZE, MainCamera,
+    game::{
+        actors::{
+            archetype::TankSpawner,
+            enemy::{DEFAULT_ENEMY_ARCHETYPE, spawn_enemy},
+            health::{DeathEvent, HealthSet},
+            player::Player,
+        },
+        controls::{Action, ActionState},
+        map_objects::spawn::{EnemySpawn, PlayerSpawn},
+    },
+    utils::region_deactivation::RegionAware,
+};
+
+const WORLD_PATH: &str = "tiles/learn.world";
+const MENU_MAP_PATH: &str = "tiles/menu.tmx";
+
+/// Camera position and zoom while the menu map is shown.
+const MENU_CAMERA_POSITION: Vec2 = Vec2::new(192.0, 192.0);
+const MENU_CAMERA_SCALE: f32 = 0.3;
+
+pub struct GameStatePlugin;
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .add_computed_state::<InGame>()
+            .add_systems(Startup, load_level_assets)
+            .add_systems(Update, finish_loading.run_if(in_state(GameState::Loading)))
+            .add_systems(OnEnter(GameState::MainMenu), spawn_menu_map)
+            .add_systems(
+                PostUpdate,
+                spawn_attract_tanks
+                    .after(TransformSystems::Propagate)
+                    .run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(OnEnter(InGame), spawn_world)
+            .add_systems(OnEnter(GameState::Paused), pause_time)
+            .add_systems(OnExit(GameState::Paused), resume_time)
+            .add_systems(
+                Update,
+                toggle_pause.run_if(in_state(GameState::Playing).or(in_state(GameState::Paused))),
+            )
+            .add_systems(
+                FixedPostUpdate,
+                game_over_on_player_death
+                    .after(HealthSet::Damage)
+                    .before(HealthSet::Cleanup)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameState {
+    /// Waiting for the world and the menu map to load.
+    #[default]
+    Loading,
+    MainMenu,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+/// The level exists: playing, paused or looking at the game over screen.
+/// Everything spawned for the level is despawned when this state is left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InGame;
+
+impl ComputedStates for InGame {
+    type SourceStates = GameState;
+
+    fn compute(state: GameState) -> Option<Self> {
+        matches!(
+            state,
+            GameState::Playing | GameState::Paused | GameState::GameOver
+        )
+        .then_some(InGame)
+    }
+}
+
+/// Actors drive around: on the level and in the attract mode behind the main menu.
+pub fn actors_active(state: Res<State<GameState>>) -> bool {
+    matches!(state.get(), GameState::Playing | GameState::MainMenu)
+}
+
+/// Spawn point of the menu map that already got its attract-mode tank.
+#[derive(Component)]
+struct AttractSpawned;
+
+/// Handles kept alive for the whole session, so going back to the menu doesn't reload them.
+#[derive(Resource)]
+struct LevelAssets {
+    world: Handle<TiledWorldAsset>,
+    menu_map: Handle<TiledMapAsset>,
+}
+
+fn load_level_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LevelAssets {
+        world: asset_server.load(WORLD_PATH),
+        menu_map: asset_server.load(MENU_MAP_PATH),
+    });
+}
+
+fn finish_loading(
+    asset_server: Res<AssetServer>,
+    level_assets: Res<LevelAssets>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if asset_server.is_loaded_with_dependencies(&level_assets.world)
+        && asset_server.is_loaded_with_dependencies(&level_assets.menu_map)
+    {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn spawn_menu_map(
+    mut commands: Commands,
+    level_assets: Res<LevelAssets>,
+    mut camera: Single<(&mut Transform, &mut Projection), With<MainCamera>>,
+) {
+    commands.spawn((
+        TiledMap(level_assets.menu_map.clone()),
+        TilemapAnchor::BottomLeft,
+        DespawnOnExit(GameState::MainMenu),
+    ));
+
+    camera.0.translation = MENU_CAMERA_POSITION.extend(camera.0.translation.z);
+    if let Projection::Orthographic(ortho) = &mut *camera.1 {
+        ortho.scale = MENU_CAMERA_SCALE;
+    }
+}
+
+/// Attract mode: every spawn point of the menu map gets an AI tank patrolling behind the menu.
+/// The tanks don't shoot, weapons only work while playing.
+fn spawn_attract_tanks(
+    mut commands: Commands,
+    mut tank_spawner: TankSpawner,
+    spawns: Query<
+        (Entity, &GlobalTransform, Option<&EnemySpawn>),
+        (
+            Or<(With<PlayerSpawn>, With<EnemySpawn>)>,
+            Without<AttractSpawned>,
+        ),
+    >,
+) {
+    if !tank_spawner.is_ready() {
+        return;
+    }
+
+    for (entity, transform, enemy_spawn) in spawns.iter() {
+        commands.entity(entity).insert(AttractSpawned);
+
+        let archetype =
+            enemy_spawn.map_or(DEFAULT_ENEMY_ARCHETYPE, |spawn| spawn.archetype.as_str());
+        let position = transform.translation().truncate();
+        let Some(tank) = spawn_enemy(&mut commands, &mut tank_spawner, archetype, position) else {
+            warn!("Can't spawn attract-mode tank {archetype:?} on the menu map");
+            continue;
+        };
+
+        // Игрока в меню нет, усыплять танки по расстоянию до него не нужно
+        commands
+            .entity(tank)
+            .insert(DespawnOnExit(GameState::MainMenu))
+            .remove::<RegionAware>();
+    }
+}
+
+fn spawn_world(mut commands: Commands, level_assets: Res<LevelAssets>) {
+    commands.spawn((
+        TiledWorld(level_assets.world.clone()),
+        TilemapAnchor::BottomLeft,
+        TiledWorldChunking::new(MAP_CHUNK_SIZE, MAP_CHUNK_SIZE),
+        TiledPhysicsSettings::<TiledPhysicsAvianBackend> {
+            backend: TiledPhysicsAvianBackend::Polyline,
+            ..default()
+        },
+        DespawnOnExit(InGame),
+    ));
+}
+
+fn toggle_pause(
+    players: Query<&ActionState>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !players
+        .iter()
+        .any(|actions| actions.just_pressed(Action::Pause))
+    {
+        return;
+    }
+
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
+
+// Физика и таймеры живут в виртуальном времени, остановка часов замораживает весь уровень
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn game_over_on_player_death(
+    mut death_events: MessageReader<DeathEvent>,
+    players: Query<(), With<Player>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if death_events
+        .read()
+        .any(|event| players.contains(event.entity))
+    {
+        next_state.set(GameState::GameOver);
+    }
+}
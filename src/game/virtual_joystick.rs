@@ -0,0 +1,249 @@
+use bevy::{input::InputSystems, prelude::*, window::PrimaryWindow};
+
+use crate::game::{
+    controls::{Action, UpdateActions, VirtualActions, stick_action},
+    state::InGame,
+};
+
+const BACKGROUND_PATH: &str = "images/joystick_background.png";
+/// Strip of 16x16 dots: blue, green and red, each idle and pressed.
+const BUTTONS_PATH: &str = "images/joystick.png";
+const BUTTON_FRAME_SIZE: u32 = 16;
+const BUTTON_FRAMES: u32 = 6;
+const KNOB_IDLE_FRAME: usize = 0;
+const KNOB_ACTIVE_FRAME: usize = 1;
+const FIRE_IDLE_FRAME: usize = 4;
+const FIRE_PRESSED_FRAME: usize = 5;
+
+/// Sizes in logical pixels.
+const MARGIN: f32 = 24.0;
+const BASE_SIZE: f32 = 160.0;
+const KNOB_SIZE: f32 = 56.0;
+const FIRE_SIZE: f32 = 80.0;
+/// How far the knob moves away from the center.
+const KNOB_TRAVEL: f32 = (BASE_SIZE - KNOB_SIZE) / 2.0;
+/// Part of the knob travel ignored around the center.
+const DEAD_ZONE: f32 = 0.25;
+
+pub struct VirtualJoystickPlugin;
+
+impl Plugin for VirtualJoystickPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JoystickState>()
+            .add_systems(OnEnter(InGame), spawn_joystick)
+            .add_systems(OnExit(InGame), release_joystick)
+            .add_systems(
+                PreUpdate,
+                read_virtual_joystick
+                    .after(InputSystems)
+                    .before(UpdateActions)
+                    .run_if(in_state(InGame)),
+            )
+            .add_systems(Update, update_joystick_visuals.run_if(in_state(InGame)));
+    }
+}
+
+/// Mouse or one of the fingers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pointer {
+    Mouse,
+    Touch(u64),
+}
+
+#[derive(Resource, Default, Debug)]
+struct JoystickState {
+    /// Pointer holding the knob.
+    pointer: Option<Pointer>,
+    /// Knob offset from the center, in window coordinates (y goes down).
+    offset: Vec2,
+    fire: bool,
+}
+
+#[derive(Component)]
+struct JoystickKnob;
+
+#[derive(Component)]
+struct FireButton;
+
+fn spawn_joystick(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let buttons = asset_server.load(BUTTONS_PATH);
+    let layout = atlas_layouts.add(TextureAtlasLayout::from_grid(
+        UVec2::splat(BUTTON_FRAME_SIZE),
+        BUTTON_FRAMES,
+        1,
+        None,
+        None,
+    ));
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(MARGIN),
+                bottom: Val::Px(MARGIN),
+                width: Val::Px(BASE_SIZE),
+                height: Val::Px(BASE_SIZE),
+                ..default()
+            },
+            ImageNode::new(asset_server.load(BACKGROUND_PATH)),
+            DespawnOnExit(InGame),
+        ))
+        .with_child((
+            JoystickKnob,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(KNOB_TRAVEL),
+                top: Val::Px(KNOB_TRAVEL),
+                width: Val::Px(KNOB_SIZE),
+                height: Val::Px(KNOB_SIZE),
+                ..default()
+            },
+            ImageNode::from_atlas_image(
+                buttons.clone(),
+                TextureAtlas {
+                    layout: layout.clone(),
+                    index: KNOB_IDLE_FRAME,
+                },
+            ),
+        ));
+
+    commands.spawn((
+        FireButton,
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(MARGIN),
+            bottom: Val::Px(MARGIN),
+            width: Val::Px(FIRE_SIZE),
+            height: Val::Px(FIRE_SIZE),
+            ..default()
+        },
+        ImageNode::from_atlas_image(
+            buttons,
+            TextureAtlas {
+                layout,
+                index: FIRE_IDLE_FRAME,
+            },
+        ),
+        DespawnOnExit(InGame),
+    ));
+}
+
+fn release_joystick(mut state: ResMut<JoystickState>, mut virtual_actions: ResMut<VirtualActions>) {
+    *state = JoystickState::default();
+    virtual_actions.0.clear();
+}
+
+/// Turns the mouse and touches over the on-screen controls into the same actions as the keys.
+fn read_virtual_joystick(
+    window: Single<&Window, With<PrimaryWindow>>,
+    touches: Res<Touches>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut state: ResMut<JoystickState>,
+    mut virtual_actions: ResMut<VirtualActions>,
+) {
+    let size = window.size();
+    let base_center = Vec2::new(MARGIN + BASE_SIZE / 2.0, size.y - MARGIN - BASE_SIZE / 2.0);
+    let fire_center = Vec2::new(
+        size.x - MARGIN - FIRE_SIZE / 2.0,
+        size.y - MARGIN - FIRE_SIZE / 2.0,
+    );
+
+    // (указатель, позиция, только что нажат)
+    let mut pointers: Vec<(Pointer, Vec2, bool)> = touches
+        .iter()
+        .map(|touch| {
+            (
+                Pointer::Touch(touch.id()),
+                touch.position(),
+                touches.just_pressed(touch.id()),
+            )
+        })
+        .collect();
+    if mouse.pressed(MouseButton::Left)
+        && let Some(cursor) = window.cursor_position()
+    {
+        pointers.push((
+            Pointer::Mouse,
+            cursor,
+            mouse.just_pressed(MouseButton::Left),
+        ));
+    }
+
+    if let Some(pointer) = state.pointer
+        && !pointers.iter().any(|(other, _, _)| *other == pointer)
+    {
+        state.pointer = None;
+    }
+
+    if state.pointer.is_none() {
+        state.pointer = pointers
+            .iter()
+            .find(|(_, position, just_pressed)| {
+                *just_pressed && position.distance(base_center) <= BASE_SIZE / 2.0
+            })
+            .map(|(pointer, _, _)| *pointer);
+    }
+
+    state.offset = state
+        .pointer
+        .and_then(|pointer| pointers.iter().find(|(other, _, _)| *other == pointer))
+        .map_or(Vec2::ZERO, |(_, position, _)| {
+            (*position - base_center).clamp_length_max(KNOB_TRAVEL)
+        });
+
+    let joystick_pointer = state.pointer;
+    state.fire = pointers.iter().any(|(pointer, position, _)| {
+        Some(*pointer) != joystick_pointer && position.distance(fire_center) <= FIRE_SIZE / 2.0
+    });
+
+    virtual_actions.0.clear();
+
+    // В окне y направлен вниз, в мире — вверх
+    let stick = Vec2::new(state.offset.x, -state.offset.y) / KNOB_TRAVEL;
+    if let Some(action) = stick_action(stick, DEAD_ZONE) {
+        virtual_actions.0.insert(action);
+    }
+
+    if state.fire {
+        virtual_actions.0.insert(Action::Fire);
+    }
+}
+
+fn update_joystick_visuals(
+    state: Res<JoystickState>,
+    mut knob: Single<(&mut Node, &mut ImageNode), (With<JoystickKnob>, Without<FireButton>)>,
+    mut fire_button: Single<&mut ImageNode, (With<FireButton>, Without<JoystickKnob>)>,
+) {
+    let (node, image) = &mut *knob;
+    node.left = Val::Px(KNOB_TRAVEL + state.offset.x);
+    node.top = Val::Px(KNOB_TRAVEL + state.offset.y);
+    set_frame(
+        image,
+        if state.pointer.is_some() {
+            KNOB_ACTIVE_FRAME
+        } else {
+            KNOB_IDLE_FRAME
+        },
+    );
+
+    set_frame(
+        &mut fire_button,
+        if state.fire {
+            FIRE_PRESSED_FRAME
+        } else {
+            FIRE_IDLE_FRAME
+        },
+    );
+}
+
+fn set_frame(image: &mut ImageNode, frame: usize) {
+    if let Some(atlas) = &mut image.texture_atlas
+        && atlas.index != frame
+    {
+        atlas.index = frame;
+    }
+}
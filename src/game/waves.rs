@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+
+use crate::game::{
+    actors::{archetype::TankSpawner, enemy::spawn_enemy, health::Dead},
+    map_objects::spawn::EnemySpawn,
+    state::{GameState, InGame},
+};
+
+/// Pause before the next wave starts.
+const WAVE_DELAY: f32 = 3.0;
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WaveController>()
+            .add_message::<WaveStarted>()
+            .add_message::<WaveEnded>()
+            .add_message::<WaveCleared>()
+            .add_systems(
+                PostUpdate,
+                (start_wave, spawn_wave_enemies, check_wave_cleared)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(InGame), reset_waves);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaveState {
+    /// Counting down to the next wave.
+    #[default]
+    Waiting,
+    /// Spawn points are still producing enemies.
+    Spawning,
+    /// Everything is spawned, waiting for the wave to be destroyed.
+    Fighting,
+    /// No spawn point takes part in the next wave.
+    Finished,
+}
+
+#[derive(Resource)]
+pub struct WaveController {
+    /// Current wave number, starting from 1.
+    pub wave: u32,
+    pub state: WaveState,
+    pub delay: Timer,
+}
+
+impl Default for WaveController {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            state: WaveState::Waiting,
+            delay: Timer::from_seconds(WAVE_DELAY, TimerMode::Once),
+        }
+    }
+}
+
+/// All spawn points of the wave started producing enemies.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct WaveStarted {
+    pub wave: u32,
+}
+
+/// The last enemy of the wave has been spawned.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct WaveEnded {
+    pub wave: u32,
+}
+
+/// Every enemy of the wave has been destroyed.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct WaveCleared {
+    pub wave: u32,
+}
+
+/// Wave the enemy was spawned in.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct WaveMember(pub u32);
+
+/// Progress of a spawn point during the current wave.
+#[derive(Component)]
+pub struct SpawnerState {
+    spawned: u32,
+    timer: Timer,
+}
+
+fn start_wave(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut controller: ResMut<WaveController>,
+    tank_spawner: TankSpawner,
+    spawns: Query<(Entity, &EnemySpawn)>,
+    mut wave_started: MessageWriter<WaveStarted>,
+) {
+    if controller.state != WaveState::Waiting || !tank_spawner.is_ready() || spawns.is_empty() {
+        return;
+    }
+
+    if !controller.delay.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let wave = controller.wave + 1;
+    let mut has_spawns = false;
+
+    for (entity, spawn) in spawns.iter() {
+        if !spawn.is_active_in(wave) {
+            continue;
+        }
+
+        has_spawns = true;
+        commands.entity(entity).insert(SpawnerState {
+            spawned: 0,
+            timer: Timer::from_seconds(spawn.interval, TimerMode::Repeating),
+        });
+    }
+
+    if !has_spawns {
+        controller.state = WaveState::Finished;
+        return;
+    }
+
+    controller.wave = wave;
+    controller.state = WaveState::Spawning;
+    wave_started.write(WaveStarted { wave });
+}
+
+fn spawn_wave_enemies(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut controller: ResMut<WaveController>,
+    mut tank_spawner: TankSpawner,
+    mut spawns: Query<(Entity, &EnemySpawn, &mut SpawnerState, &GlobalTransform)>,
+    mut wave_ended: MessageWriter<WaveEnded>,
+) {
+    if controller.state != WaveState::Spawning {
+        return;
+    }
+
+    let mut all_done = true;
+
+    for (entity, spawn, mut state, transform) in spawns.iter_mut() {
+        state.timer.tick(time.delta());
+
+        // Первый враг появляется сразу, остальные — по таймеру
+        if state.spawned < spawn.count && (state.spawned == 0 || state.timer.just_finished()) {
+            let position = transform.translation().truncate();
+            match spawn_enemy(&mut commands, &mut tank_spawner, &spawn.archetype, position) {
+                Some(enemy) => {
+                    commands.entity(enemy).insert(WaveMember(controller.wave));
+                }
+                None => warn!(
+                    "Spawn point {entity} can't spawn archetype {:?}, skipping the enemy",
+                    spawn.archetype
+                ),
+            }
+            // Неудачная попытка тоже считается, иначе волна никогда не закончится
+            state.spawned += 1;
+        }
+
+        if state.spawned < spawn.count {
+            all_done = false;
+        } else {
+            commands.entity(entity).remove::<SpawnerState>();
+        }
+    }
+
+    if all_done {
+        controller.state = WaveState::Fighting;
+        wave_ended.write(WaveEnded {
+            wave: controller.wave,
+        });
+    }
+}
+
+fn check_wave_cleared(
+    mut controller: ResMut<WaveController>,
+    members: Query<&WaveMember, Without<Dead>>,
+    mut wave_cleared: MessageWriter<WaveCleared>,
+) {
+    if controller.state != WaveState::Fighting {
+        return;
+    }
+
+    let wave = controller.wave;
+    if members.iter().any(|member| member.0 == wave) {
+        return;
+    }
+
+    controller.state = WaveState::Waiting;
+    controller.delay.reset();
+    wave_cleared.write(WaveCleared { wave });
+}
+
+fn reset_waves(mut commands: Commands) {
+    commands.insert_resource(WaveController::default());
+}
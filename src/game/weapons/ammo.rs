@@ -0,0 +1,312 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::game::{
+    actors::{health::Dead, player::Player},
+    controls::{Action, ActionState},
+    state::GameState,
+};
+
+/// How close an actor has to drive to an ammo pickup to collect it.
+const PICKUP_RADIUS: f32 = 10.0;
+
+pub struct AmmoPlugin;
+
+impl Plugin for AmmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ReloadWeapon>()
+            .add_message::<RefillAmmo>()
+            .register_type::<Ammo>()
+            .add_systems(
+                Update,
+                (
+                    player_reload_input,
+                    collect_ammo_pickups,
+                    apply_refills,
+                    start_reloads,
+                    tick_reloads,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Magazine and reserve of a [`Weapon`](super::weapon::Weapon).
+/// Weapons without it never run out of ammo.
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct Ammo {
+    pub magazine_size: u32,
+    pub in_magazine: u32,
+    /// Rounds left outside the magazine, `None` for an endless supply.
+    pub reserve: Option<u32>,
+    /// Time to change the magazine, in seconds.
+    pub reload_time: f32,
+    #[reflect(ignore)]
+    reload: Option<Timer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AmmoState {
+    Ready,
+    /// Firing is blocked until the magazine is changed, `progress` goes from 0 to 1.
+    Reloading {
+        progress: f32,
+    },
+    /// Nothing left in the magazine and nothing to reload with.
+    Empty,
+}
+
+impl Ammo {
+    /// Starts with a full magazine.
+    pub fn new(magazine_size: u32, reserve: Option<u32>, reload_time: f32) -> Self {
+        Self {
+            magazine_size,
+            in_magazine: magazine_size,
+            reserve,
+            reload_time,
+            reload: None,
+        }
+    }
+
+    pub fn state(&self) -> AmmoState {
+        if let Some(reload) = &self.reload {
+            return AmmoState::Reloading {
+                progress: reload.fraction(),
+            };
+        }
+
+        if self.in_magazine == 0 && self.reserve == Some(0) {
+            AmmoState::Empty
+        } else {
+            AmmoState::Ready
+        }
+    }
+
+    pub fn can_fire(&self) -> bool {
+        self.reload.is_none() && self.in_magazine > 0
+    }
+
+    pub fn consume(&mut self) {
+        self.in_magazine = self.in_magazine.saturating_sub(1);
+    }
+
+    /// Returns `false` when the magazine is full, already being changed or there is nothing to load.
+    pub fn start_reload(&mut self) -> bool {
+        if self.reload.is_some()
+            || self.in_magazine >= self.magazine_size
+            || self.reserve == Some(0)
+        {
+            return false;
+        }
+
+        self.reload = Some(Timer::from_seconds(self.reload_time, TimerMode::Once));
+        true
+    }
+
+    /// Adds rounds to the reserve, endless reserves stay endless.
+    pub fn refill(&mut self, amount: u32) {
+        if let Some(reserve) = &mut self.reserve {
+            *reserve += amount;
+        }
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        let Some(reload) = &mut self.reload else {
+            return;
+        };
+
+        if !reload.tick(delta).is_finished() {
+            return;
+        }
+
+        self.reload = None;
+        let missing = self.magazine_size - self.in_magazine;
+        let loaded = match &mut self.reserve {
+            Some(reserve) => {
+                let loaded = missing.min(*reserve);
+                *reserve -= loaded;
+                loaded
+            }
+            None => missing,
+        };
+        self.in_magazine += loaded;
+    }
+}
+
+/// Asks the weapon of `entity` to change its magazine.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ReloadWeapon {
+    pub entity: Entity,
+}
+
+/// Adds `amount` rounds to the reserve of `target`.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct RefillAmmo {
+    pub target: Entity,
+    pub amount: u32,
+}
+
+/// Ammo box lying on the map, picked up by the first armed actor driving over it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct AmmoPickup {
+    pub amount: u32,
+}
+
+fn player_reload_input(
+    player: Option<Single<(Entity, &ActionState), With<Player>>>,
+    mut reloads: MessageWriter<ReloadWeapon>,
+) {
+    if let Some(player) = player
+        && player.1.just_pressed(Action::Reload)
+    {
+        reloads.write(ReloadWeapon { entity: player.0 });
+    }
+}
+
+fn collect_ammo_pickups(
+    mut commands: Commands,
+    pickups: Query<(Entity, &GlobalTransform, &AmmoPickup)>,
+    actors: Query<(Entity, &Transform), (With<Ammo>, Without<Dead>)>,
+    mut refills: MessageWriter<RefillAmmo>,
+) {
+    for (pickup_entity, pickup_transform, pickup) in pickups.iter() {
+        let pickup_position = pickup_transform.translation().truncate();
+
+        let Some((actor, _)) = actors.iter().find(|(_, transform)| {
+            transform.translation.truncate().distance(pickup_position) <= PICKUP_RADIUS
+        }) else {
+            continue;
+        };
+
+        refills.write(RefillAmmo {
+            target: actor,
+            amount: pickup.amount,
+        });
+        commands.entity(pickup_entity).despawn();
+    }
+}
+
+fn apply_refills(mut refills: MessageReader<RefillAmmo>, mut ammo: Query<&mut Ammo>) {
+    for refill in refills.read() {
+        if let Ok(mut ammo) = ammo.get_mut(refill.target) {
+            ammo.refill(refill.amount);
+        }
+    }
+}
+
+fn start_reloads(mut requests: MessageReader<ReloadWeapon>, mut ammo: Query<&mut Ammo>) {
+    for request in requests.read() {
+        if let Ok(mut ammo) = ammo.get_mut(request.entity) {
+            ammo.start_reload();
+        }
+    }
+
+    // Пустой магазин меняется сам, и у игрока, и у ИИ
+    for mut ammo in ammo.iter_mut() {
+        if ammo.in_magazine == 0 {
+            ammo.start_reload();
+        }
+    }
+}
+
+fn tick_reloads(time: Res<Time>, mut ammo: Query<&mut Ammo>) {
+    for mut ammo in ammo.iter_mut() {
+        if ammo.reload.is_some() {
+            ammo.tick(time.delta());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fire_all(ammo: &mut Ammo) -> u32 {
+        let mut shots = 0;
+        while ammo.can_fire() {
+            ammo.consume();
+            shots += 1;
+        }
+        shots
+    }
+
+    #[test]
+    fn fires_down_to_an_empty_magazine() {
+        let mut ammo = Ammo::new(3, Some(5), 1.0);
+
+        assert_eq!(fire_all(&mut ammo), 3);
+        assert_eq!(ammo.in_magazine, 0);
+        assert_eq!(ammo.state(), AmmoState::Ready);
+    }
+
+    #[test]
+    fn reload_blocks_fire_until_finished() {
+        let mut ammo = Ammo::new(3, Some(5), 1.0);
+        ammo.consume();
+
+        assert!(ammo.start_reload());
+        assert!(!ammo.start_reload());
+        assert!(!ammo.can_fire());
+
+        ammo.tick(Duration::from_secs_f32(0.5));
+        assert_eq!(ammo.state(), AmmoState::Reloading { progress: 0.5 });
+        assert!(!ammo.can_fire());
+
+        ammo.tick(Duration::from_secs_f32(0.5));
+        assert_eq!(ammo.state(), AmmoState::Ready);
+        assert_eq!(ammo.in_magazine, 3);
+        assert_eq!(ammo.reserve, Some(4));
+    }
+
+    #[test]
+    fn full_magazine_is_not_reloaded() {
+        let mut ammo = Ammo::new(3, Some(5), 1.0);
+
+        assert!(!ammo.start_reload());
+    }
+
+    #[test]
+    fn reserve_runs_out() {
+        let mut ammo = Ammo::new(3, Some(2), 1.0);
+
+        fire_all(&mut ammo);
+        assert!(ammo.start_reload());
+        ammo.tick(Duration::from_secs_f32(1.0));
+        // В запасе было меньше, чем помещается в магазин
+        assert_eq!(ammo.in_magazine, 2);
+        assert_eq!(ammo.reserve, Some(0));
+
+        fire_all(&mut ammo);
+        assert_eq!(ammo.state(), AmmoState::Empty);
+        assert!(!ammo.start_reload());
+        assert!(!ammo.can_fire());
+    }
+
+    #[test]
+    fn refill_brings_an_empty_weapon_back() {
+        let mut ammo = Ammo::new(2, Some(0), 1.0);
+        fire_all(&mut ammo);
+        assert_eq!(ammo.state(), AmmoState::Empty);
+
+        ammo.refill(5);
+        assert_eq!(ammo.state(), AmmoState::Ready);
+        assert!(ammo.start_reload());
+        ammo.tick(Duration::from_secs_f32(1.0));
+        assert_eq!(ammo.in_magazine, 2);
+        assert_eq!(ammo.reserve, Some(3));
+    }
+
+    #[test]
+    fn endless_reserve_stays_endless() {
+        let mut ammo = Ammo::new(2, None, 1.0);
+        fire_all(&mut ammo);
+
+        ammo.refill(10);
+        assert!(ammo.start_reload());
+        ammo.tick(Duration::from_secs_f32(1.0));
+        assert_eq!(ammo.in_magazine, 2);
+        assert_eq!(ammo.reserve, None);
+    }
+}
@@ -1,38 +1,62 @@
 use avian2d::prelude::{
-    AngularDamping, Collider, ColliderDisabled, CollisionLayers, Collisions, LinearDamping,
-    LinearVelocity, LockedAxes, RigidBody, RigidBodyDisabled, Sleeping, SpeculativeMargin,
+    ActiveCollisionHooks, AngularDamping, Collider, ColliderDisabled, CollisionLayers, Collisions,
+    LinearDamping, LinearVelocity, LockedAxes, RigidBody, RigidBodyDisabled, Sleeping,
+    SpeculativeMargin,
 };
-use bevy::prelude::*;
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use bevy_ecs_tiled::prelude::TiledMapAsset;
+use bevy_spritesheet_animation::prelude::{Animation, SpritesheetAnimation};
 
 use crate::{
-    MainCamera, PHYSICS_SPEED,
     game::{
         GameLayer,
         actors::{
-            movement::{LookDir, PlayerLookDir},
-            player::Player,
+            health::{Damage, DamageEvent, Health},
+            team::{FriendlyFire, HitRule, Team},
+        },
+        state::{GameState, InGame},
+        weapons::weapon::Weapon,
+    },
+    utils::{
+        pool::*,
+        region_deactivation::RegionAware,
+        tiled::{
+            destructor::TileDestructor,
+            tileset_reader::{
+                find_tileset, read_animation_handle_from_tileset, read_sprite_from_tileset,
+            },
         },
     },
-    utils::{pool::*, region_deactivation::RegionAware, tiled::destructor::TileDestructor},
 };
+
+const BULLET_TILESET: &str = "bullet";
+/// Class of the `bullet.tsx` tiles a weapon can pick, the rest are animation frames.
+const BULLET_CLASS: &str = "bullet";
+
 pub struct ShootingPlugin;
 
 impl Plugin for ShootingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(FixedUpdate, bullet_lifetime_system)
-            .add_systems(Update, shoot_system)
-            .add_plugins(PoolPlugin::<Bullet>::new(1, new_bullet))
-            .init_resource::<ShootTimer>()
-            .register_type::<Pool<Bullet>>()
-            .register_type::<TileDestructor<Bullet>>();
+        app.add_systems(
+            FixedUpdate,
+            (orient_bullets, bullet_hit_system, bullet_lifetime_system)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .init_resource::<BulletVisuals>()
+        .add_systems(PreUpdate, load_bullet_visuals)
+        .add_systems(Update, apply_bullet_visuals)
+        .add_systems(OnExit(InGame), deactivate_all_bullets)
+        .add_message::<BulletIntercepted>()
+        .add_plugins(PoolPlugin::<Bullet>::new(1, new_bullet))
+        .register_type::<Pool<Bullet>>()
+        .register_type::<TileDestructor<Bullet>>();
     }
 }
 
-#[derive(Resource, Default)]
-struct ShootTimer {
-    last_shot: f32,
-}
-
 pub const PLAYER_SIZE: Vec2 = Vec2::new(14.0, 14.0);
 
 #[derive(Component, Reflect)]
@@ -43,131 +67,336 @@ pub struct BulletData {
     pub traveled: f32,
     pub max_distance: f32,
     pub parent: Option<Entity>,
+    /// Speed given by the weapon, kept so a ricochet doesn't slow the bullet down.
+    pub speed: f32,
 }
 
-fn shoot_system(
-    mut commands: Commands,
-    keyboard: Res<ButtonInput<KeyCode>>,
-    look_dir: Res<PlayerLookDir>,
-    player_q: Single<(Entity, &Transform), With<Player>>,
-    mut pool: ResMut<Pool<Bullet>>,
-    time: Res<Time>,
-    mut shoot_timer: ResMut<ShootTimer>,
-) {
-    if !keyboard.pressed(KeyCode::Space) {
-        return;
-    }
+/// Tile of `bullet.tsx` the bullet is drawn with.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BulletTile(pub u32);
 
-    let current_time = time.elapsed_secs();
-    let cooldown = 0.5;
-    if current_time - shoot_timer.last_shot < cooldown {
-        return;
-    }
+struct BulletVisual {
+    sprite: Sprite,
+    animation: Option<Handle<Animation>>,
+}
 
-    let Some(dir) = look_dir.0 else { return };
+/// Sprites of every bullet tile, read once and shared by the pooled bullets.
+#[derive(Resource, Default)]
+struct BulletVisuals {
+    loaded: bool,
+    tiles: HashMap<u32, BulletVisual>,
+}
 
-    let dir_vec = dir.to_vec2();
-    let spawn_offset = bullet_spawn_offset(dir, PLAYER_SIZE);
+/// Two bullets from different shooters collided and destroyed each other.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct BulletIntercepted {
+    pub bullet: Entity,
+    pub other: Entity,
+    pub shooter: Option<Entity>,
+    pub other_shooter: Option<Entity>,
+    pub position: Vec2,
+}
 
-    activate_from_pool::<Bullet>(&mut commands, &mut pool, |entity, commands| {
-        let (player_entity, transform) = player_q.into_inner();
+/// Takes a bullet from the pool and launches it from the front of the shooter.
+pub fn fire_bullet(
+    commands: &mut Commands,
+    pool: &mut Pool<Bullet>,
+    shooter: Entity,
+    team: Option<Team>,
+    origin: Vec3,
+    dir_vec: Vec2,
+    weapon: &Weapon,
+) {
+    let spawn_offset = bullet_spawn_offset(dir_vec, PLAYER_SIZE);
 
+    activate_from_pool::<Bullet>(commands, pool, |entity, commands| {
         commands
             .entity(entity)
             .insert((
-                Transform::from_translation(transform.translation + spawn_offset),
-                LinearVelocity(dir_vec * 800.0),
+                Transform::from_translation(origin + spawn_offset)
+                    .with_rotation(bullet_rotation(dir_vec)),
+                LinearVelocity(dir_vec * weapon.projectile_speed),
+                BulletTile(weapon.bullet_tile),
                 TileDestructor::<Bullet> {
                     remove_on_contact: true,
                     vector: dir_vec,
+                    ricochets: weapon.ricochets,
+                    blast: weapon.blast,
+                    power: weapon.power,
                     remove_fn: Some(bullet_remove_on_contact),
                 },
                 BulletData {
                     traveled: 0.0,
-                    max_distance: 1000.0 / PHYSICS_SPEED,
-                    parent: Some(player_entity),
+                    max_distance: weapon.range,
+                    parent: Some(shooter),
+                    speed: weapon.projectile_speed,
                 },
+                Damage(weapon.damage),
             ))
             .remove::<ColliderDisabled>()
             .remove::<RigidBodyDisabled>();
+
+        match team {
+            Some(team) => commands.entity(entity).insert(team),
+            None => commands.entity(entity).remove::<Team>(),
+        };
     });
+}
+
+/// Bullet art points up, turn it towards the travel direction.
+fn bullet_rotation(dir: Vec2) -> Quat {
+    match dir.try_normalize() {
+        Some(dir) => Quat::from_rotation_arc_2d(Vec2::Y, dir),
+        None => Quat::IDENTITY,
+    }
+}
+
+fn load_bullet_visuals(
+    mut visuals: ResMut<BulletVisuals>,
+    tiled_map_assets: Res<Assets<TiledMapAsset>>,
+    mut animations: ResMut<Assets<Animation>>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    images: Res<Assets<Image>>,
+) {
+    if visuals.loaded {
+        return;
+    }
+
+    let Some(tileset) = find_tileset(BULLET_TILESET, &tiled_map_assets) else {
+        return;
+    };
+
+    for (tile_id, tile) in tileset.tiles() {
+        if tile.user_type.as_deref() != Some(BULLET_CLASS) {
+            continue;
+        }
+
+        let visual = if tile.animation.is_some() {
+            read_animation_handle_from_tileset(
+                BULLET_TILESET.to_string(),
+                tile_id,
+                &tiled_map_assets,
+                &mut animations,
+                &mut atlas_layouts,
+                &images,
+            )
+            .map(|(sprite, animation, _)| BulletVisual {
+                sprite,
+                animation: Some(animation),
+            })
+        } else {
+            read_sprite_from_tileset(
+                BULLET_TILESET.to_string(),
+                tile_id,
+                &tiled_map_assets,
+                &mut atlas_layouts,
+            )
+            .map(|sprite| BulletVisual {
+                sprite,
+                animation: None,
+            })
+        };
+
+        if let Some(visual) = visual {
+            visuals.tiles.insert(tile_id, visual);
+        }
+    }
+
+    visuals.loaded = true;
+}
+
+fn apply_bullet_visuals(
+    mut commands: Commands,
+    visuals: Res<BulletVisuals>,
+    bullets: Query<(Entity, Ref<BulletTile>), With<Active<Bullet>>>,
+) {
+    // Пули, выпущенные до загрузки bullet.tsx, перерисовываются, когда он загрузится
+    let reload_all = visuals.is_changed();
+
+    for (entity, tile) in bullets.iter() {
+        if !reload_all && !tile.is_changed() {
+            continue;
+        }
+
+        let mut entity_commands = commands.entity(entity);
+
+        let Some(visual) = visuals.tiles.get(&tile.0) else {
+            entity_commands
+                .insert(fallback_bullet_sprite())
+                .remove::<SpritesheetAnimation>();
+            continue;
+        };
+
+        entity_commands.insert(visual.sprite.clone());
+        match &visual.animation {
+            Some(animation) => entity_commands.insert(SpritesheetAnimation::new(animation.clone())),
+            None => entity_commands.remove::<SpritesheetAnimation>(),
+        };
+    }
+}
+
+/// Keeps the sprite looking along the velocity, e.g. after a ricochet.
+fn orient_bullets(
+    mut bullets: Query<
+        (&LinearVelocity, &mut Transform),
+        (With<Active<Bullet>>, Changed<LinearVelocity>),
+    >,
+) {
+    for (velocity, mut transform) in bullets.iter_mut() {
+        if velocity.0 != Vec2::ZERO {
+            transform.rotation = bullet_rotation(velocity.0);
+        }
+    }
+}
 
-    shoot_timer.last_shot = current_time;
+fn fallback_bullet_sprite() -> Sprite {
+    Sprite {
+        color: Color::srgb(0.9, 0.9, 0.9),
+        custom_size: Some(Vec2::splat(2.0)),
+        ..default()
+    }
 }
 
 fn bullet_remove_on_contact(commands: &mut Commands, bullet: Entity, pool: &mut Pool<Bullet>) {
-    deactivate_to_pool(commands, pool, bullet, |entity, commands| {
-        commands
-            .entity(entity)
-            .insert((
-                LinearVelocity(Vec2::ZERO),
-                ColliderDisabled,
-                RigidBodyDisabled,
-            ))
-            .remove::<TileDestructor<Bullet>>();
-    });
+    bullet_deactivate(commands, pool, bullet);
 }
 
 pub fn bullet_lifetime_system(
     mut commands: Commands,
     time: Res<Time>,
-    camera: Single<&Transform, (With<Camera2d>, With<MainCamera>)>,
     mut bullets: Query<
-        (Entity, &LinearVelocity, &Transform, &mut BulletData),
+        (Entity, &LinearVelocity, &mut BulletData),
         (With<Active<Bullet>>, Without<Sleeping>),
     >,
-    collisions: Collisions,
     mut pool: ResMut<Pool<Bullet>>,
 ) {
-    for (entity, vel, bullet_transform, mut bullet) in bullets.iter_mut() {
+    for (entity, vel, mut bullet) in bullets.iter_mut() {
         bullet.traveled += vel.0.length() * time.delta_secs();
 
-        let should_be_removed = bullet.traveled >= bullet.max_distance;
-
-        // if !should_be_removed {
-        //     for collision in collisions.collisions_with(entity) {
-        //         // Столкновение не с объектом, выпустившем пулю, а с чем-то ещё
-        //         if collision.body1 != bullet.parent && collision.body2 != bullet.parent {
-        //             // should_be_removed = true;
-        //             break;
-        //         }
-        //     }
-        // }
-
-        if should_be_removed {
-            deactivate_to_pool::<Bullet>(&mut commands, &mut pool, entity, |entity, commands| {
-                commands
-                    .entity(entity)
-                    .insert((
-                        LinearVelocity(Vec2::ZERO),
-                        ColliderDisabled,
-                        RigidBodyDisabled,
-                    ))
-                    .remove::<TileDestructor<Bullet>>();
-            });
+        if bullet.traveled >= bullet.max_distance {
+            bullet_deactivate(&mut commands, &mut pool, entity);
         }
     }
 }
 
-fn bullet_spawn_offset(dir: LookDir, player_size: Vec2) -> Vec3 {
-    let half = player_size / 2.0;
+fn deactivate_all_bullets(
+    mut commands: Commands,
+    bullets: Query<Entity, With<Active<Bullet>>>,
+    mut pool: ResMut<Pool<Bullet>>,
+) {
+    for entity in bullets.iter() {
+        bullet_deactivate(&mut commands, &mut pool, entity);
+    }
+}
+
+/// Damages bodies with [`Health`] touched by a bullet, ignoring the one that fired it.
+/// Bullets of different shooters cancel each other out.
+pub fn bullet_hit_system(
+    mut commands: Commands,
+    bullets: Query<
+        (Entity, &Transform, &BulletData, &Damage, Option<&Team>),
+        (With<Active<Bullet>>, Without<Sleeping>),
+    >,
+    targets: Query<Option<&Team>, With<Health>>,
+    other_bullets: Query<(&BulletData, Option<&Team>), With<Active<Bullet>>>,
+    friendly_fire: Res<FriendlyFire>,
+    collisions: Collisions,
+    mut pool: ResMut<Pool<Bullet>>,
+    mut damage_events: MessageWriter<DamageEvent>,
+    mut interceptions: MessageWriter<BulletIntercepted>,
+) {
+    let mut spent = HashSet::new();
+
+    for (entity, transform, bullet, damage, team) in bullets.iter() {
+        if spent.contains(&entity) {
+            continue;
+        }
+
+        for collision in collisions.collisions_with(entity) {
+            if collision.manifolds.is_empty() {
+                continue;
+            }
+
+            let other = if collision.body1 == Some(entity) {
+                collision.body2
+            } else {
+                collision.body1
+            };
+
+            let Some(other) = other else {
+                continue;
+            };
+
+            if let Ok((other_bullet, other_team)) = other_bullets.get(other) {
+                // Свои снаряды, в том числе дробь одного выстрела, друг друга не сбивают
+                let friendly = friendly_fire.projectiles_pass(
+                    bullet.parent,
+                    team.copied(),
+                    other_bullet.parent,
+                    other_team.copied(),
+                );
+                if friendly || !spent.insert(other) {
+                    continue;
+                }
 
-    match dir {
-        LookDir::Up => Vec3::new(0.0, half.y, 0.0),
-        LookDir::Down => Vec3::new(0.0, -half.y, 0.0),
-        LookDir::Left => Vec3::new(-half.x, 0.0, 0.0),
-        LookDir::Right => Vec3::new(half.x, 0.0, 0.0),
+                spent.insert(entity);
+                interceptions.write(BulletIntercepted {
+                    bullet: entity,
+                    other,
+                    shooter: bullet.parent,
+                    other_shooter: other_bullet.parent,
+                    position: transform.translation.truncate(),
+                });
+                bullet_deactivate(&mut commands, &mut pool, entity);
+                bullet_deactivate(&mut commands, &mut pool, other);
+                break;
+            }
+
+            let Ok(target_team) = targets.get(other) else {
+                continue;
+            };
+
+            match friendly_fire.rule(bullet.parent, team.copied(), other, target_team.copied()) {
+                HitRule::Ignore => continue,
+                HitRule::Stop => {}
+                HitRule::Damage => {
+                    damage_events.write(DamageEvent {
+                        target: other,
+                        source: bullet.parent,
+                        amount: damage.0,
+                    });
+                }
+            }
+
+            bullet_deactivate(&mut commands, &mut pool, entity);
+            break;
+        }
     }
 }
 
+fn bullet_deactivate(commands: &mut Commands, pool: &mut Pool<Bullet>, bullet: Entity) {
+    deactivate_to_pool::<Bullet>(commands, pool, bullet, |entity, commands| {
+        commands
+            .entity(entity)
+            .insert((
+                LinearVelocity(Vec2::ZERO),
+                ColliderDisabled,
+                RigidBodyDisabled,
+            ))
+            .remove::<TileDestructor<Bullet>>();
+    });
+}
+
+pub fn bullet_spawn_offset(dir: Vec2, player_size: Vec2) -> Vec3 {
+    let half = player_size / 2.0;
+
+    (dir * half).extend(0.0)
+}
+
 fn new_bullet(commands: &mut Commands) -> Entity {
     commands
         .spawn((
-            Sprite {
-                color: Color::srgb(0.9, 0.9, 0.9),
-                custom_size: Some(Vec2::splat(2.0)),
-                ..default()
-            },
+            fallback_bullet_sprite(),
             RigidBody::Dynamic,
             LinearVelocity(Vec2::ZERO),
             Collider::rectangle(2.0, 2.0),
@@ -182,12 +411,18 @@ fn new_bullet(commands: &mut Commands) -> Entity {
             //SweptCcd::LINEAR,
             RegionAware,
             Visibility::Hidden,
-            CollisionLayers::new(GameLayer::Player, [GameLayer::Player, GameLayer::Bricks]),
+            CollisionLayers::new(
+                GameLayer::Projectile,
+                [GameLayer::Player, GameLayer::Bricks, GameLayer::Projectile],
+            ),
+            ActiveCollisionHooks::MODIFY_CONTACTS,
             BulletData {
                 traveled: 0.0,
                 max_distance: 0.0,
                 parent: None,
+                speed: 0.0,
             },
+            Damage::default(),
         ))
         .id()
 }
@@ -0,0 +1,443 @@
+use avian2d::prelude::{
+    Collider, ColliderDisabled, CollidingEntities, CollisionEventsEnabled, CollisionLayers,
+    RigidBody, Sensor,
+};
+use bevy::prelude::*;
+use bevy_ecs_tiled::prelude::*;
+
+use crate::{
+    game::{
+        GameLayer,
+        actors::{
+            health::{DamageEvent, Dead, Health},
+            player::Player,
+            team::{FriendlyFire, HitRule, Team},
+        },
+        controls::{Action, ActionState},
+        state::{GameState, InGame},
+    },
+    utils::{
+        pool::*,
+        tiled::destructor::{AffectedByDestructor, Blast, TileBlast, get_tile_pos_from_world_pos},
+    },
+};
+
+/// Charges lie on the ground under the tanks.
+const CHARGE_Z: f32 = -150.5;
+const CHARGE_SIZE: f32 = 6.0;
+
+pub struct ChargePlugin;
+
+impl Plugin for ChargePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<PlaceCharge>()
+            .add_plugins(PoolPlugin::<Charge>::new(8, new_charge))
+            .register_type::<SecondaryWeapon>()
+            .add_systems(OnExit(InGame), deactivate_all_charges)
+            .add_systems(
+                Update,
+                (player_place_input, place_charges)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                detonate_charges.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub enum ChargeKind {
+    /// Goes off when anyone but its owner drives over it.
+    Mine,
+    /// Goes off `fuse` seconds after being placed.
+    Timed { fuse: f32 },
+}
+
+/// Secondary weapon that drops static charges on the map instead of firing projectiles.
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct SecondaryWeapon {
+    pub kind: ChargeKind,
+    /// Delay between two charges, in seconds.
+    pub cooldown: f32,
+    pub damage: f32,
+    /// Tiles and actors inside the footprint are hit on detonation.
+    pub blast: Blast,
+    pub power: u32,
+    /// Charges of one owner lying on the map at the same time.
+    pub max_placed: u32,
+    last_used: Option<f32>,
+}
+
+impl SecondaryWeapon {
+    pub fn new(kind: ChargeKind, blast: Blast) -> Self {
+        Self {
+            kind,
+            cooldown: 1.0,
+            damage: 2.0,
+            blast,
+            power: 0,
+            max_placed: 3,
+            last_used: None,
+        }
+    }
+
+    pub fn is_ready(&self, now: f32) -> bool {
+        self.last_used
+            .is_none_or(|last_used| now - last_used >= self.cooldown)
+    }
+}
+
+/// Asks `owner` to drop a charge of its [`SecondaryWeapon`] under itself.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PlaceCharge {
+    pub owner: Entity,
+}
+
+#[derive(Component, Reflect)]
+pub struct Charge;
+
+#[derive(Component)]
+struct ChargeData {
+    owner: Entity,
+    team: Option<Team>,
+    kind: ChargeKind,
+    fuse: Option<Timer>,
+    damage: f32,
+    blast: Blast,
+    power: u32,
+}
+
+/// Cells of a destructible layer. Charges lie in the middle of a cell, and blasts
+/// are measured in cells the same way [`TileBlast`] measures them for the tiles.
+#[derive(Clone, Copy)]
+struct TileGrid {
+    transform: GlobalTransform,
+    size: TilemapSize,
+    grid: TilemapGridSize,
+    tile: TilemapTileSize,
+    map_type: TilemapType,
+    anchor: TilemapAnchor,
+}
+
+type TileGridQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static GlobalTransform,
+        &'static TilemapSize,
+        &'static TilemapGridSize,
+        &'static TilemapTileSize,
+        &'static TilemapType,
+        &'static TilemapAnchor,
+    ),
+    With<AffectedByDestructor>,
+>;
+
+impl TileGrid {
+    fn all(layers: &TileGridQuery) -> impl Iterator<Item = TileGrid> {
+        layers.iter().map(
+            |(&transform, &size, &grid, &tile, &map_type, &anchor)| TileGrid {
+                transform,
+                size,
+                grid,
+                tile,
+                map_type,
+                anchor,
+            },
+        )
+    }
+
+    /// Cell under `position`, `None` outside of the layer.
+    fn cell(&self, position: Vec2) -> Option<TilePos> {
+        get_tile_pos_from_world_pos(
+            self.transform,
+            position,
+            self.size,
+            self.grid,
+            self.tile,
+            self.map_type,
+            self.anchor,
+        )
+    }
+
+    fn center(&self, cell: TilePos) -> Vec2 {
+        let local = cell.center_in_world(
+            &self.size,
+            &self.grid,
+            &self.tile,
+            &self.map_type,
+            &self.anchor,
+        );
+        self.transform.transform_point(local.extend(0.0)).truncate()
+    }
+
+    /// Center of the cell under `position`.
+    fn snap(&self, position: Vec2) -> Option<Vec2> {
+        self.cell(position).map(|cell| self.center(cell))
+    }
+
+    /// Whether `target` stands in a cell of the `blast` footprint around the cell under `impact`.
+    fn blast_covers(&self, blast: &Blast, impact: Vec2, target: Vec2) -> bool {
+        let Some(cell) = self.cell(impact) else {
+            return false;
+        };
+
+        // Цель может стоять за краем слоя, поэтому считаем клетки от центра, а не через TilePos
+        let offset = (target - self.center(cell)) / Vec2::new(self.grid.x, self.grid.y);
+        blast.covers(offset.round().as_ivec2())
+    }
+}
+
+fn player_place_input(
+    player: Option<Single<(Entity, &ActionState), (With<Player>, With<SecondaryWeapon>)>>,
+    mut requests: MessageWriter<PlaceCharge>,
+) {
+    if let Some(player) = player
+        && player.1.just_pressed(Action::AltFire)
+    {
+        requests.write(PlaceCharge { owner: player.0 });
+    }
+}
+
+fn place_charges(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut requests: MessageReader<PlaceCharge>,
+    mut owners: Query<(&Transform, &mut SecondaryWeapon, Option<&Team>), Without<Dead>>,
+    charges: Query<(&Transform, &ChargeData), With<Active<Charge>>>,
+    layers: TileGridQuery,
+    mut pool: ResMut<Pool<Charge>>,
+) {
+    let now = time.elapsed_secs();
+
+    for request in requests.read() {
+        let Ok((transform, mut weapon, team)) = owners.get_mut(request.owner) else {
+            continue;
+        };
+
+        if !weapon.is_ready(now) {
+            continue;
+        }
+
+        // Заряд ложится в клетку самой крупной сетки, чтобы оказаться посреди кирпича
+        let position = transform.translation.truncate();
+        let position = TileGrid::all(&layers)
+            .filter_map(|grid| Some((grid.grid.x.max(grid.grid.y), grid.snap(position)?)))
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map_or(position, |(_, snapped)| snapped);
+
+        let placed = charges
+            .iter()
+            .filter(|(_, charge)| charge.owner == request.owner)
+            .count();
+        // Одна клетка — один заряд
+        let occupied = charges
+            .iter()
+            .any(|(transform, _)| transform.translation.truncate() == position);
+        if placed >= weapon.max_placed as usize || occupied {
+            continue;
+        }
+
+        let charge = ChargeData {
+            owner: request.owner,
+            team: team.copied(),
+            kind: weapon.kind,
+            fuse: match weapon.kind {
+                ChargeKind::Mine => None,
+                ChargeKind::Timed { fuse } => Some(Timer::from_seconds(fuse, TimerMode::Once)),
+            },
+            damage: weapon.damage,
+            blast: weapon.blast,
+            power: weapon.power,
+        };
+
+        activate_from_pool(&mut commands, &mut pool, |entity, commands| {
+            commands
+                .entity(entity)
+                .insert((
+                    Transform::from_translation(position.extend(CHARGE_Z)),
+                    charge_sprite(charge.kind),
+                    charge,
+                ))
+                .remove::<ColliderDisabled>();
+        });
+        weapon.last_used = Some(now);
+    }
+}
+
+fn detonate_charges(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut charges: Query<
+        (Entity, &Transform, &mut ChargeData, &CollidingEntities),
+        With<Active<Charge>>,
+    >,
+    targets: Query<(Entity, &GlobalTransform, Option<&Team>), (With<Health>, Without<Dead>)>,
+    friendly_fire: Res<FriendlyFire>,
+    layers: TileGridQuery,
+    mut pool: ResMut<Pool<Charge>>,
+    mut tile_blasts: MessageWriter<TileBlast>,
+    mut damage_events: MessageWriter<DamageEvent>,
+) {
+    for (entity, transform, mut charge, colliding) in charges.iter_mut() {
+        let triggered = match charge.kind {
+            ChargeKind::Mine => colliding.iter().any(|&other| other != charge.owner),
+            ChargeKind::Timed { .. } => charge
+                .fuse
+                .as_mut()
+                .is_none_or(|fuse| fuse.tick(time.delta()).is_finished()),
+        };
+
+        if !triggered {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+
+        tile_blasts.write(TileBlast {
+            position,
+            blast: charge.blast,
+            damage: charge.damage,
+            power: charge.power,
+        });
+
+        for (target, target_transform, target_team) in targets.iter() {
+            // Танк задевает взрыв, если стоит в клетке, где взрыв ломает тайлы
+            let target_position = target_transform.translation().truncate();
+            if !TileGrid::all(&layers)
+                .any(|grid| grid.blast_covers(&charge.blast, position, target_position))
+            {
+                continue;
+            }
+
+            let rule = friendly_fire.rule(
+                Some(charge.owner),
+                charge.team,
+                target,
+                target_team.copied(),
+            );
+            if rule == HitRule::Damage {
+                damage_events.write(DamageEvent {
+                    target,
+                    source: Some(charge.owner),
+                    amount: charge.damage,
+                });
+            }
+        }
+
+        charge_deactivate(&mut commands, &mut pool, entity);
+    }
+}
+
+fn deactivate_all_charges(
+    mut commands: Commands,
+    charges: Query<Entity, With<Active<Charge>>>,
+    mut pool: ResMut<Pool<Charge>>,
+) {
+    for entity in charges.iter() {
+        charge_deactivate(&mut commands, &mut pool, entity);
+    }
+}
+
+fn charge_deactivate(commands: &mut Commands, pool: &mut Pool<Charge>, charge: Entity) {
+    deactivate_to_pool(commands, pool, charge, |entity, commands| {
+        commands
+            .entity(entity)
+            .insert(ColliderDisabled)
+            .remove::<ChargeData>();
+    });
+}
+
+fn charge_sprite(kind: ChargeKind) -> Sprite {
+    let color = match kind {
+        ChargeKind::Mine => Color::srgb(0.6, 0.15, 0.1),
+        ChargeKind::Timed { .. } => Color::srgb(0.9, 0.55, 0.1),
+    };
+
+    Sprite {
+        color,
+        custom_size: Some(Vec2::splat(CHARGE_SIZE)),
+        ..default()
+    }
+}
+
+fn new_charge(commands: &mut Commands) -> Entity {
+    commands
+        .spawn((
+            Charge,
+            charge_sprite(ChargeKind::Mine),
+            RigidBody::Static,
+            Sensor,
+            Collider::circle(CHARGE_SIZE / 2.0),
+            CollisionLayers::new(GameLayer::Charge, [GameLayer::Player]),
+            CollisionEventsEnabled,
+            CollidingEntities::default(),
+            ColliderDisabled,
+            Visibility::Hidden,
+        ))
+        .id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tiled::destructor::BlastShape;
+
+    /// 16x16 cells of 4 pixels, like the layers of `new.tmx`.
+    fn grid() -> TileGrid {
+        TileGrid {
+            transform: GlobalTransform::from_translation(Vec3::new(-10.0, 6.0, 0.0)),
+            size: TilemapSize { x: 16, y: 16 },
+            grid: TilemapGridSize { x: 4.0, y: 4.0 },
+            tile: TilemapTileSize { x: 4.0, y: 4.0 },
+            map_type: TilemapType::Square,
+            anchor: TilemapAnchor::BottomLeft,
+        }
+    }
+
+    #[test]
+    fn charges_snap_to_map_cells() {
+        let grid = grid();
+        let position = grid.center(TilePos::new(5, 7)) + Vec2::new(1.5, -1.5);
+
+        let snapped = grid.snap(position).unwrap();
+
+        assert_eq!(snapped, grid.center(TilePos::new(5, 7)));
+        assert_eq!(grid.snap(snapped), Some(snapped));
+        // Соседняя клетка в 4 пикселях, а не в 16
+        assert_eq!(
+            grid.snap(snapped + Vec2::new(4.0, 0.0)).unwrap() - snapped,
+            Vec2::new(4.0, 0.0)
+        );
+        assert_eq!(grid.snap(Vec2::new(-100.0, 0.0)), None);
+    }
+
+    #[test]
+    fn actors_are_hit_in_the_cells_where_tiles_break() {
+        let grid = grid();
+        let impact_cell = TilePos::new(8, 8);
+        let impact = grid.center(impact_cell) + Vec2::new(-1.0, 1.0);
+
+        for shape in [BlastShape::Circle, BlastShape::Square] {
+            let blast = Blast { radius: 2, shape };
+
+            for x in 0..grid.size.x {
+                for y in 0..grid.size.y {
+                    let offset = IVec2::new(x as i32 - 8, y as i32 - 8);
+                    let center = grid.center(TilePos::new(x, y));
+
+                    // Танк в любой точке клетки задет так же, как тайл этой клетки
+                    for nudge in [Vec2::ZERO, Vec2::new(1.9, -1.9), Vec2::new(-1.9, 1.9)] {
+                        assert_eq!(
+                            grid.blast_covers(&blast, impact, center + nudge),
+                            blast.covers(offset),
+                            "{shape:?} {offset} {nudge}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1 +1,4 @@
+pub mod ammo;
 pub mod bullet;
+pub mod charge;
+pub mod weapon;
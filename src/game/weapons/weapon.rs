@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    PHYSICS_SPEED,
+    game::{
+        actors::{health::Dead, movement::Facing, player::Player, team::Team},
+        controls::{Action, ActionState},
+        state::GameState,
+        weapons::{
+            ammo::Ammo,
+            bullet::{Bullet, fire_bullet},
+        },
+    },
+    utils::{pool::Pool, tiled::destructor::Blast},
+};
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<FireWeapon>()
+            .add_message::<ShotFired>()
+            .register_type::<Weapon>()
+            .add_systems(
+                Update,
+                (player_fire_input, fire_weapons)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Anything that can shoot: tanks, turrets. Fired with a [`FireWeapon`] message.
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct Weapon {
+    /// Shots per second.
+    pub fire_rate: f32,
+    pub projectile_speed: f32,
+    /// Distance a projectile flies before it disappears.
+    pub range: f32,
+    /// Width of the firing cone, in degrees.
+    pub spread: f32,
+    /// Projectiles per shot, spread evenly over the cone.
+    pub projectile_count: u32,
+    pub damage: f32,
+    /// How many times a projectile bounces off armored tiles before it is absorbed.
+    pub ricochets: u32,
+    /// Explosive projectiles blast every destructible tile around the impact.
+    pub blast: Option<Blast>,
+    /// Tile of `bullet.tsx` the projectiles are drawn with.
+    pub bullet_tile: u32,
+    /// Armor-piercing level, projectiles break armored tiles with armor up to this value.
+    pub power: u32,
+    last_shot: Option<f32>,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self {
+            fire_rate: 2.0,
+            projectile_speed: 800.0,
+            range: 1000.0 / PHYSICS_SPEED,
+            spread: 0.0,
+            projectile_count: 1,
+            damage: 1.0,
+            ricochets: 0,
+            blast: None,
+            bullet_tile: 0,
+            power: 0,
+            last_shot: None,
+        }
+    }
+}
+
+impl Weapon {
+    pub fn cooldown(&self) -> f32 {
+        1.0 / self.fire_rate.max(f32::EPSILON)
+    }
+
+    pub fn is_ready(&self, now: f32) -> bool {
+        self.last_shot
+            .is_none_or(|last_shot| now - last_shot >= self.cooldown())
+    }
+
+    /// Directions of every projectile of one shot aimed at `direction`.
+    fn projectile_directions(&self, direction: Vec2) -> Vec<Vec2> {
+        let count = self.projectile_count.max(1);
+        let spread = self.spread.to_radians();
+
+        if count == 1 {
+            // Одиночный выстрел — случайное отклонение внутри конуса
+            let angle = if spread > 0.0 {
+                rand::rng().random_range(-spread / 2.0..=spread / 2.0)
+            } else {
+                0.0
+            };
+            return vec![Vec2::from_angle(angle).rotate(direction)];
+        }
+
+        let step = spread / (count - 1) as f32;
+        (0..count)
+            .map(|i| Vec2::from_angle(-spread / 2.0 + step * i as f32).rotate(direction))
+            .collect()
+    }
+}
+
+/// Asks `shooter` to fire its [`Weapon`] towards `direction`. Ignored while the weapon reloads.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct FireWeapon {
+    pub shooter: Entity,
+    pub direction: Vec2,
+}
+
+/// A shot has just left the barrel.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ShotFired {
+    pub shooter: Entity,
+    pub position: Vec2,
+    pub direction: Vec2,
+}
+
+fn player_fire_input(
+    players: Query<(Entity, &ActionState, &Facing), With<Player>>,
+    mut fire: MessageWriter<FireWeapon>,
+) {
+    for (player, actions, facing) in players.iter() {
+        if actions.pressed(Action::Fire) {
+            fire.write(FireWeapon {
+                shooter: player,
+                direction: facing.0.to_vec2(),
+            });
+        }
+    }
+}
+
+fn fire_weapons(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut requests: MessageReader<FireWeapon>,
+    mut weapons: Query<(&Transform, &mut Weapon, Option<&Team>, Option<&mut Ammo>), Without<Dead>>,
+    mut pool: ResMut<Pool<Bullet>>,
+    mut shots: MessageWriter<ShotFired>,
+) {
+    let now = time.elapsed_secs();
+
+    for request in requests.read() {
+        let Ok((transform, mut weapon, team, ammo)) = weapons.get_mut(request.shooter) else {
+            continue;
+        };
+
+        let direction = request.direction.normalize_or_zero();
+        if direction == Vec2::ZERO || !weapon.is_ready(now) {
+            continue;
+        }
+
+        if let Some(mut ammo) = ammo {
+            if !ammo.can_fire() {
+                continue;
+            }
+            ammo.consume();
+        }
+
+        for projectile_dir in weapon.projectile_directions(direction) {
+            fire_bullet(
+                &mut commands,
+                &mut pool,
+                request.shooter,
+                team.copied(),
+                transform.translation,
+                projectile_dir,
+                &weapon,
+            );
+        }
+
+        weapon.last_shot = Some(now);
+        shots.write(ShotFired {
+            shooter: request.shooter,
+            position: transform.translation.truncate(),
+            direction,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn three_pellets_keep_configured_spread() {
+        let weapon = Weapon {
+            spread: 30.0,
+            projectile_count: 3,
+            ..default()
+        };
+
+        let angles: Vec<f32> = weapon
+            .projectile_directions(Vec2::Y)
+            .iter()
+            .map(|dir| Vec2::Y.angle_to(*dir).to_degrees())
+            .collect();
+
+        assert_eq!(angles.len(), 3);
+        for (angle, expected) in angles.iter().zip([-15.0, 0.0, 15.0]) {
+            assert!((angle - expected).abs() < 1e-3, "{angles:?}");
+        }
+    }
+}
@@ -1,11 +1,27 @@
 mod game;
 mod utils;
 
+use crate::game::actors::archetype::TankArchetypePlugin;
+use crate::game::actors::enemy::EnemyPlugin;
+use crate::game::actors::health::HealthPlugin;
 use crate::game::actors::movement::*;
 use crate::game::actors::player::*;
+use crate::game::actors::team::TeamPlugin;
+use crate::game::actors::wreck::WreckPlugin;
+use crate::game::audio::GameAudioPlugin;
+use crate::game::collision_hooks::{GameCollisionHooks, GameCollisionHooksPlugin};
+use crate::game::controls::ControlsPlugin;
+use crate::game::effects::EffectsPlugin;
 use crate::game::map_objects::MapObjectsPlugin;
 use crate::game::map_tiles::MapTilesPlugin;
+use crate::game::menu::MenuPlugin;
+use crate::game::state::{GameState, GameStatePlugin, actors_active};
+use crate::game::virtual_joystick::VirtualJoystickPlugin;
+use crate::game::waves::WavePlugin;
+use crate::game::weapons::ammo::AmmoPlugin;
 use crate::game::weapons::bullet::*;
+use crate::game::weapons::charge::ChargePlugin;
+use crate::game::weapons::weapon::WeaponPlugin;
 use crate::utils::camera::*;
 use crate::utils::region_deactivation::RegionActivationPlugin;
 use crate::utils::tiled::destructor::*;
@@ -39,6 +55,7 @@ fn main() {
         .add_plugins((
             EguiPlugin::default(),
             PhysicsPlugins::default()
+                .with_collision_hooks::<GameCollisionHooks>()
                 .with_length_unit(1.)
                 .set(PhysicsInterpolationPlugin::interpolate_all()),
             TiledPhysicsPlugin::<TiledPhysicsAvianBackend>::default(),
@@ -51,52 +68,49 @@ fn main() {
         ))
         .insert_resource(Time::<Physics>::default().with_relative_speed(PHYSICS_SPEED))
         .add_plugins((
+            GameStatePlugin,
+            MenuPlugin,
+            ControlsPlugin,
+            VirtualJoystickPlugin,
+        ))
+        .add_plugins((TeamPlugin, AmmoPlugin, ChargePlugin))
+        .add_plugins((
+            TankArchetypePlugin,
+            HealthPlugin,
+            EnemyPlugin,
+            WavePlugin,
+            WreckPlugin,
+            EffectsPlugin,
+            GameAudioPlugin,
             TileDestructorPlugin,
+            GameCollisionHooksPlugin,
             ShootingPlugin,
+            WeaponPlugin,
             RegionActivationPlugin,
             MapObjectsPlugin,
             MapTilesPlugin,
             GameCameraPlugin,
         ))
         .insert_resource(Gravity(Vec2::ZERO))
-        .insert_resource(LastMoveDir::default())
-        .init_resource::<PlayerLookDir>()
-        .init_resource::<PlayerMoving>()
         .add_systems(Startup, (init).chain())
-        .add_systems(PostUpdate, spawn_player)
+        .add_systems(
+            PostUpdate,
+            spawn_player.run_if(in_state(GameState::Playing)),
+        )
         .add_systems(
             FixedUpdate,
             (
-                move_player,
-                apply_player_look_dir.after(move_player),
-                player_animation_controller.after(move_player),
-            ),
+                player_move_intent.before(apply_move_intent),
+                apply_move_intent,
+                apply_facing.after(apply_move_intent),
+                movement_animation_controller.after(apply_move_intent),
+            )
+                .run_if(actors_active),
         )
         // .add_systems(PostUpdate, update_camera_position)
         .run();
 }
 
-fn init(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn init(mut commands: Commands) {
     commands.spawn((Camera2d, MainCamera));
-
-    commands.spawn((
-        Text::new("Move the light with WASD.\nThe camera will smoothly track the light."),
-        Node {
-            position_type: PositionType::Absolute,
-            bottom: px(12),
-            left: px(12),
-            ..default()
-        },
-    ));
-
-    // Load and spawn the world
-    commands.spawn((
-        TiledWorld(asset_server.load("tiles/learn.world")),
-        TilemapAnchor::BottomLeft,
-        TiledWorldChunking::new(MAP_CHUNK_SIZE, MAP_CHUNK_SIZE),
-        TiledPhysicsSettings::<TiledPhysicsAvianBackend> {
-            backend: TiledPhysicsAvianBackend::Polyline,
-            ..default()
-        },
-    ));
 }
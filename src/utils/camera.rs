@@ -1,5 +1,7 @@
-use crate::game::actors::movement::{LastMoveDir, PlayerLookDir, PlayerMoving};
+use crate::game::actors::archetype::TankArchetype;
+use crate::game::actors::movement::{Facing, Moving};
 use crate::game::actors::player::Player;
+use crate::game::state::InGame;
 use crate::{CAMERA_DECAY_RATE, MainCamera};
 use bevy::app::{Plugin, PostUpdate};
 use bevy::camera::{Camera2d, OrthographicProjection, Projection};
@@ -10,7 +12,9 @@ use bevy::ecs::system::{Query, ResMut};
 use bevy::input::mouse::AccumulatedMouseScroll;
 use bevy::math::FloatExt;
 use bevy::math::{Vec2, Vec3};
-use bevy::prelude::{Res, Single, StableInterpolate, Time, Transform, With};
+use bevy::prelude::{
+    IntoScheduleConfigs, Res, Single, StableInterpolate, Time, Transform, With, in_state,
+};
 use bevy::time::{Timer, TimerMode};
 
 const LOOK_FORWARD_DISTANCE: f32 = 48.0;
@@ -22,7 +26,7 @@ pub struct GameCameraPlugin;
 
 impl Plugin for GameCameraPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(PostUpdate, update_camera_position);
+        app.add_systems(PostUpdate, update_camera_position.run_if(in_state(InGame)));
         app.insert_resource(CameraZoomState {
             mode: ZoomMode::Moving,
             switch_timer: Timer::from_seconds(2.0, TimerMode::Once), // задержка 2 сек
@@ -44,10 +48,13 @@ pub enum ZoomMode {
 
 pub fn update_camera_position(
     mut camera: Single<(&mut Transform, &mut Projection), (With<Camera2d>, With<MainCamera>)>,
-    player: Option<Single<&Transform, (With<Player>, Without<Camera2d>)>>,
+    player: Option<
+        Single<
+            (&Transform, &Facing, &Moving, Option<&TankArchetype>),
+            (With<Player>, Without<Camera2d>),
+        >,
+    >,
     time: Res<Time>,
-    look_dir: Res<PlayerLookDir>,
-    player_moving: Res<PlayerMoving>,
     mut zoom_state: ResMut<CameraZoomState>,
 ) {
     let Some(player) = player else {
@@ -64,10 +71,13 @@ pub fn update_camera_position(
         return;
     };
 
-    let idle_zoom = BASE_ZOOM * 1.1;
+    let (player, facing, player_moving, archetype) = player.into_inner();
+    let base_zoom = archetype.map_or(BASE_ZOOM, TankArchetype::camera_scale);
+    let decay_rate = archetype.map_or(CAMERA_DECAY_RATE, TankArchetype::camera_decay_rate);
+    let idle_zoom = base_zoom * 1.1;
 
     // ===== MODE SWITCH WITH DELAY =====
-    let desired_mode = if player_moving.is_moving {
+    let desired_mode = if player_moving.0 {
         ZoomMode::Moving
     } else {
         ZoomMode::Idle
@@ -86,7 +96,7 @@ pub fn update_camera_position(
 
     // ===== ZOOM =====
     let target_zoom = match zoom_state.mode {
-        ZoomMode::Moving => BASE_ZOOM,
+        ZoomMode::Moving => base_zoom,
         ZoomMode::Idle => idle_zoom,
     };
 
@@ -97,18 +107,15 @@ pub fn update_camera_position(
     }
 
     // ===== POSITION =====
-    let target_point = match look_dir.0 {
-        Some(look_direction) => look_direction.to_vec3().mul_add(
-            Vec3::new(LOOK_FORWARD_DISTANCE, LOOK_FORWARD_DISTANCE, 0.0),
-            player.translation,
-        ),
-        None => player.translation,
-    };
+    let target_point = facing.0.to_vec3().mul_add(
+        Vec3::new(LOOK_FORWARD_DISTANCE, LOOK_FORWARD_DISTANCE, 0.0),
+        player.translation,
+    );
 
     let direction = Vec3::new(target_point.x, target_point.y, camera.0.translation.z);
 
     camera
         .0
         .translation
-        .smooth_nudge(&direction, CAMERA_DECAY_RATE, time.delta_secs());
+        .smooth_nudge(&direction, decay_rate, time.delta_secs());
 }
@@ -1,4 +1,5 @@
 pub mod camera;
+pub mod pathfinding;
 pub mod pool;
 pub mod region_deactivation;
 pub mod tiled;
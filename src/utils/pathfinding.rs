@@ -0,0 +1,223 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use bevy::prelude::*;
+
+/// Cost of stepping into a free cell.
+const BASE_COST: u32 = 1;
+/// Cost of stepping into a cell that has to be shot through first.
+const DESTRUCTIBLE_COST: u32 = 8;
+
+const NEIGHBOURS: [IVec2; 4] = [IVec2::Y, IVec2::NEG_Y, IVec2::NEG_X, IVec2::X];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavCell {
+    /// Can be passed after destroying it, e.g. a brick.
+    Destructible,
+    /// Can never be passed, e.g. water or armored bricks.
+    Obstacle,
+}
+
+/// Coarse world-space grid used by actors to find their way around the map.
+/// Cells that are not stored are free.
+#[derive(Resource)]
+pub struct NavGrid {
+    pub cell_size: f32,
+    cells: HashMap<IVec2, NavCell>,
+}
+
+impl NavGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    /// Marks the cell, obstacles always win over destructible cells.
+    pub fn mark(&mut self, cell: IVec2, kind: NavCell) {
+        let entry = self.cells.entry(cell).or_insert(kind);
+        if kind == NavCell::Obstacle {
+            *entry = kind;
+        }
+    }
+
+    pub fn get(&self, cell: IVec2) -> Option<NavCell> {
+        self.cells.get(&cell).copied()
+    }
+
+    pub fn world_to_cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    pub fn cell_center(&self, cell: IVec2) -> Vec2 {
+        (cell.as_vec2() + Vec2::splat(0.5)) * self.cell_size
+    }
+
+    fn step_cost(&self, cell: IVec2) -> Option<u32> {
+        match self.get(cell) {
+            None => Some(BASE_COST),
+            Some(NavCell::Destructible) => Some(DESTRUCTIBLE_COST),
+            Some(NavCell::Obstacle) => None,
+        }
+    }
+
+    /// A* search over the four grid directions. Returns cell centers from the first step
+    /// to the goal. If the goal can't be reached within `max_nodes` expanded cells,
+    /// the path leads to the explored cell closest to it.
+    pub fn find_path(&self, from: Vec2, to: Vec2, max_nodes: usize) -> Option<Vec<Vec2>> {
+        let start = self.world_to_cell(from);
+        let goal = self.world_to_cell(to);
+
+        if start == goal {
+            return Some(Vec::new());
+        }
+
+        let heuristic = |cell: IVec2| {
+            let diff = (goal - cell).abs();
+            (diff.x + diff.y) as u32 * BASE_COST
+        };
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<IVec2, IVec2> = HashMap::new();
+        let mut cost_so_far: HashMap<IVec2, u32> = HashMap::new();
+
+        open.push(Reverse((heuristic(start), start.x, start.y)));
+        cost_so_far.insert(start, 0);
+
+        let mut closest = start;
+        let mut expanded = 0;
+
+        while let Some(Reverse((_, x, y))) = open.pop() {
+            let current = IVec2::new(x, y);
+
+            if current == goal {
+                closest = goal;
+                break;
+            }
+
+            expanded += 1;
+            if expanded > max_nodes {
+                break;
+            }
+
+            if heuristic(current) < heuristic(closest) {
+                closest = current;
+            }
+
+            let current_cost = cost_so_far[&current];
+
+            for offset in NEIGHBOURS {
+                let next = current + offset;
+                // Цель доступна всегда: это позиция игрока или точка патруля
+                let step = if next == goal {
+                    BASE_COST
+                } else {
+                    match self.step_cost(next) {
+                        Some(step) => step,
+                        None => continue,
+                    }
+                };
+
+                let new_cost = current_cost + step;
+                if cost_so_far.get(&next).is_some_and(|&cost| cost <= new_cost) {
+                    continue;
+                }
+
+                cost_so_far.insert(next, new_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((new_cost + heuristic(next), next.x, next.y)));
+            }
+        }
+
+        if closest == start {
+            return None;
+        }
+
+        let mut path = vec![self.cell_center(closest)];
+        let mut current = closest;
+        while let Some(&previous) = came_from.get(&current) {
+            if previous == start {
+                break;
+            }
+            path.push(self.cell_center(previous));
+            current = previous;
+        }
+        path.reverse();
+
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(grid: &NavGrid, x: i32, y: i32) -> Vec2 {
+        grid.cell_center(IVec2::new(x, y))
+    }
+
+    #[test]
+    fn straight_path_in_open_grid() {
+        let grid = NavGrid::new(16.0);
+
+        let path = grid.find_path(cell(&grid, 0, 0), cell(&grid, 3, 0), 100);
+
+        assert_eq!(
+            path,
+            Some(vec![
+                cell(&grid, 1, 0),
+                cell(&grid, 2, 0),
+                cell(&grid, 3, 0)
+            ])
+        );
+    }
+
+    #[test]
+    fn goes_around_obstacles() {
+        let mut grid = NavGrid::new(16.0);
+        for y in -2..=2 {
+            grid.mark(IVec2::new(1, y), NavCell::Obstacle);
+        }
+
+        let path = grid
+            .find_path(cell(&grid, 0, 0), cell(&grid, 2, 0), 100)
+            .unwrap();
+
+        assert_eq!(path.last(), Some(&cell(&grid, 2, 0)));
+        assert!(
+            path.iter()
+                .all(|&point| grid.get(grid.world_to_cell(point)).is_none())
+        );
+    }
+
+    #[test]
+    fn max_nodes_leads_to_the_closest_explored_cell() {
+        let grid = NavGrid::new(16.0);
+
+        let path = grid
+            .find_path(cell(&grid, 0, 0), cell(&grid, 100, 0), 5)
+            .unwrap();
+
+        assert_eq!(
+            path,
+            vec![
+                cell(&grid, 1, 0),
+                cell(&grid, 2, 0),
+                cell(&grid, 3, 0),
+                cell(&grid, 4, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn no_path_when_nothing_is_explored() {
+        let grid = NavGrid::new(16.0);
+
+        assert_eq!(grid.find_path(Vec2::ZERO, Vec2::splat(1000.0), 0), None);
+    }
+}
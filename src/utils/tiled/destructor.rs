@@ -1,12 +1,16 @@
+use std::collections::{HashMap, HashSet};
+
 use avian2d::prelude::*;
 use bevy::app::Plugin;
 use bevy::ecs::relationship::Relationship;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_ecs_tiled::physics::RemovedTilesStorage;
 use bevy_ecs_tiled::prelude::*;
 use bevy_ecs_tiled::tiled::event::TilemapUpdatedMarker;
 
-use crate::game::weapons::bullet::Bullet;
+use crate::game::actors::health::Damage;
+use crate::game::weapons::bullet::{Bullet, BulletData};
 use crate::utils::pool::Pool;
 
 #[derive(Default)]
@@ -14,7 +18,12 @@ pub struct TileDestructorPlugin;
 
 impl Plugin for TileDestructorPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(FixedPreUpdate, destructor_remove_tiles);
+        app.add_message::<TileHit>()
+            .add_message::<TileBlast>()
+            .add_systems(
+                FixedPreUpdate,
+                (destructor_remove_tiles, apply_tile_blasts).chain(),
+            );
     }
 }
 
@@ -23,6 +32,13 @@ impl Plugin for TileDestructorPlugin {
 pub struct TileDestructor<P: Component + Send + Sync + 'static> {
     pub remove_on_contact: bool,
     pub vector: Vec2,
+    /// Bounces left off tiles that survive the hit. While above zero the destructor
+    /// is deflected instead of removed, 0 turns ricochets off.
+    pub ricochets: u32,
+    /// Explosive destructors blow up on the first tile they touch, see [`TileBlast`].
+    pub blast: Option<Blast>,
+    /// Compared against the tile armor, see [`TileHealthStorage::hit`].
+    pub power: u32,
     #[reflect(ignore)]
     pub remove_fn: Option<fn(&mut Commands, Entity, &mut Pool<P>)>,
 }
@@ -32,49 +48,253 @@ impl<P: Component + Send + Sync + 'static> Default for TileDestructor<P> {
         Self {
             remove_on_contact: false,
             vector: Vec2::ZERO,
+            ricochets: 0,
+            blast: None,
+            power: 0,
             remove_fn: None,
         }
     }
 }
 
-
-
 #[allow(dead_code)]
 #[derive(Component, Default)]
 pub struct AffectedByDestructor {
     pub layer_id: u32,
 }
 
+/// A destructor hit a tile, `destroyed` tells whether the tile was removed.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct TileHit {
+    pub position: Vec2,
+    pub tilemap: Entity,
+    pub tile_pos: TilePos,
+    pub destroyed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum BlastShape {
+    #[default]
+    Circle,
+    Square,
+}
+
+/// Footprint of an explosion, in tiles around the impact tile.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct Blast {
+    pub radius: u32,
+    pub shape: BlastShape,
+}
+
+impl Blast {
+    /// Whether the tile `offset` tiles away from the impact is inside the footprint.
+    pub fn covers(&self, offset: IVec2) -> bool {
+        let radius = self.radius as i32;
+
+        match self.shape {
+            BlastShape::Circle => offset.length_squared() <= radius * radius,
+            BlastShape::Square => offset.x.abs() <= radius && offset.y.abs() <= radius,
+        }
+    }
+}
+
+/// Damages every destructible tile inside the blast footprint around `position`.
+/// Removed tiles of a layer are rebuilt in one batch.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct TileBlast {
+    pub position: Vec2,
+    pub blast: Blast,
+    pub damage: f32,
+    pub power: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileHitOutcome {
+    /// The armor is stronger than the projectile, the tile is untouched.
+    Blocked,
+    Damaged,
+    Destroyed,
+}
+
+/// Remaining hit points and armor of destructible tiles, keyed by their position in the layer.
+/// Tiles without an entry are destroyed by the first hit.
+#[derive(Component, Default)]
+pub struct TileHealthStorage {
+    health: HashMap<TilePos, f32>,
+    armor: HashMap<TilePos, u32>,
+}
+
+impl TileHealthStorage {
+    pub fn set(&mut self, pos: TilePos, health: f32) {
+        self.health.insert(pos, health);
+    }
+
+    pub fn get(&self, pos: &TilePos) -> Option<f32> {
+        self.health.get(pos).copied()
+    }
+
+    pub fn set_armor(&mut self, pos: TilePos, armor: u32) {
+        self.armor.insert(pos, armor);
+    }
+
+    /// Armor of the tile, 0 for plain tiles.
+    pub fn armor(&self, pos: &TilePos) -> u32 {
+        self.armor.get(pos).copied().unwrap_or(0)
+    }
+
+    /// Hit by a projectile with the given `power`. Armored tiles ignore weaker projectiles
+    /// and break at once when the power is enough, plain tiles lose `damage` health.
+    pub fn hit(&mut self, pos: &TilePos, damage: f32, power: u32) -> TileHitOutcome {
+        let armor = self.armor(pos);
+
+        if armor > 0 {
+            if power < armor {
+                return TileHitOutcome::Blocked;
+            }
+
+            self.health.remove(pos);
+            self.armor.remove(pos);
+            return TileHitOutcome::Destroyed;
+        }
+
+        if self.damage(pos, damage) {
+            TileHitOutcome::Destroyed
+        } else {
+            TileHitOutcome::Damaged
+        }
+    }
+
+    /// Subtracts `amount` from the tile and returns `true` if it has no health left.
+    pub fn damage(&mut self, pos: &TilePos, amount: f32) -> bool {
+        let Some(health) = self.health.get_mut(pos) else {
+            return true;
+        };
+
+        *health -= amount;
+        if *health <= 0.0 {
+            self.health.remove(pos);
+            return true;
+        }
+
+        false
+    }
+}
+
+/// Tile layers that destructors and blasts can damage.
+type DestructibleLayers<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static ChildOf,
+        &'static TilemapSize,
+        &'static TilemapGridSize,
+        &'static TilemapTileSize,
+        &'static TilemapType,
+        &'static mut TileStorage,
+        &'static GlobalTransform,
+        &'static TilemapAnchor,
+        &'static TiledMapReference,
+        &'static AffectedByDestructor,
+        Has<TilemapUpdatedMarker>,
+        Option<&'static mut TileHealthStorage>,
+    ),
+    With<AffectedByDestructor>,
+>;
+
+/// Removes destroyed tiles so that chunk reloads and collider rebuilds skip them.
+#[derive(SystemParam)]
+struct TileRemover<'w, 's> {
+    commands: Commands<'w, 's>,
+    maps: Query<'w, 's, (&'static TiledMap, &'static TiledMapStorage), Without<RespawnTiledMap>>,
+    removed_tiles: ResMut<'w, RemovedTilesStorage>,
+    map_assets: Res<'w, Assets<TiledMapAsset>>,
+}
+
+impl TileRemover<'_, '_> {
+    /// Despawns the tile at `pos` and marks its layer for the rebuild.
+    fn remove(
+        &mut self,
+        tilemap: Entity,
+        storage: &mut TileStorage,
+        pos: TilePos,
+        map_reference: &TiledMapReference,
+        layer_id: u32,
+        is_updated: bool,
+    ) {
+        let Some(tile_entity) = storage.get(&pos) else {
+            return;
+        };
+
+        self.commands.entity(tile_entity).despawn();
+        storage.remove(&pos);
+
+        if let Ok(map_handle) = self.maps.get(map_reference.entity())
+            && let Some(map_asset) = self.map_assets.get(map_handle.0.id())
+        {
+            self.removed_tiles.add_tile(map_asset, layer_id, &pos);
+        }
+
+        self.commands
+            .entity(tilemap)
+            .insert_if(TilemapUpdatedMarker { layer_id }, || !is_updated);
+    }
+}
+
 #[allow(dead_code)]
 #[allow(clippy::too_many_arguments)]
 fn destructor_remove_tiles(
     mut commands: Commands,
-    q_destructor: Query<(Entity, &TileDestructor<Bullet>), With<TileDestructor<Bullet>>>,
-    q_maps: Query<(&TiledMap, &TiledMapStorage), Without<RespawnTiledMap>>,
-    mut q_tiled_tilemap: Query<
-        (
-            Entity,
-            &ChildOf,
-            &TilemapSize,
-            &TilemapGridSize,
-            &TilemapTileSize,
-            &TilemapType,
-            &mut TileStorage,
-            &GlobalTransform,
-            &TilemapAnchor,
-            &TiledMapReference,
-            &AffectedByDestructor,
-            Option<&TilemapUpdatedMarker>,
-        ),
-        With<AffectedByDestructor>,
-    >,
+    mut q_destructor: Query<(
+        Entity,
+        &mut TileDestructor<Bullet>,
+        Option<&Damage>,
+        Option<&mut LinearVelocity>,
+        Option<&BulletData>,
+    )>,
+    mut q_tiled_tilemap: DestructibleLayers,
     q_colliders: Query<&ChildOf, With<Collider>>,
     collisions: Collisions,
-    mut removed_tiles: ResMut<RemovedTilesStorage>,
-    map_assets: Res<Assets<TiledMapAsset>>,
+    mut tile_remover: TileRemover,
     mut pool: ResMut<Pool<Bullet>>,
+    mut tile_hits: MessageWriter<TileHit>,
+    mut tile_blasts: MessageWriter<TileBlast>,
 ) {
-    for (destructor_entity, destructor_config) in q_destructor {
+    for (destructor_entity, mut destructor_config, damage, velocity, bullet_data) in
+        q_destructor.iter_mut()
+    {
+        let damage = damage.map_or(1.0, |damage| damage.0);
+
+        if let Some(blast) = destructor_config.blast {
+            let impact = collisions
+                .collisions_with(destructor_entity)
+                .filter(|collision| {
+                    q_colliders.contains(collision.collider1)
+                        || q_colliders.contains(collision.collider2)
+                })
+                .flat_map(|collision| &collision.manifolds)
+                .flat_map(|manifold| &manifold.points)
+                .map(|point| point.point)
+                .next();
+
+            if let Some(position) = impact {
+                tile_blasts.write(TileBlast {
+                    position,
+                    blast,
+                    damage,
+                    power: destructor_config.power,
+                });
+                match destructor_config.remove_fn {
+                    Some(function) => function(&mut commands, destructor_entity, &mut pool),
+                    None => commands.entity(destructor_entity).despawn(),
+                }
+            }
+            continue;
+        }
+
+        let mut hit_tiles: HashSet<(Entity, TilePos)> = HashSet::new();
+        let mut destroyed_any = false;
+        let mut deflect_normal = None;
+
         for collision in collisions.collisions_with(destructor_entity) {
             let collider_entity = if let Some(body1) = collision.body1 {
                 if body1 == destructor_entity {
@@ -92,6 +312,16 @@ fn destructor_remove_tiles(
             };
 
             for manifold in &collision.manifolds {
+                // Нормаль от снаряда к тайлу; от уже отражённого снаряда тайл не получает урон
+                let normal = if collision.body1 == Some(destructor_entity) {
+                    manifold.normal
+                } else {
+                    -manifold.normal
+                };
+                if normal.dot(destructor_config.vector) < 0.0 {
+                    continue;
+                }
+
                 for point in &manifold.points {
                     for (
                         tilemap,
@@ -106,6 +336,7 @@ fn destructor_remove_tiles(
                         map_reference,
                         affected_by_destructor,
                         is_updated,
+                        mut health_storage,
                     ) in q_tiled_tilemap.iter_mut()
                     {
                         if parent_of_collider != parent_of_layer {
@@ -140,53 +371,190 @@ fn destructor_remove_tiles(
                             //     penteration_point
                             // ));
 
-                            if let Some(tile_entity) = storage.get(&pos) {
-                                commands.entity(tile_entity).despawn();
-                                storage.remove(&pos);
-
-                                if let Ok(map_handle) = q_maps.get(map_reference.entity())
-                                    && let Some(map_asset) = map_assets.get(map_handle.0.id())
-                                {
-                                    removed_tiles.add_tile(
-                                        map_asset,
-                                        affected_by_destructor.layer_id,
-                                        &pos,
-                                    );
-                                }
-
-                                commands.entity(collider_entity).queue_silenced(
-                                    |entity_commands: EntityWorldMut<'_>| {
-                                        entity_commands.despawn();
-                                    },
-                                );
-
-                                commands.entity(tilemap).insert_if(
-                                    TilemapUpdatedMarker {
-                                        layer_id: affected_by_destructor.layer_id,
-                                    },
-                                    || is_updated.is_none(),
-                                );
-
-                                if destructor_config.remove_on_contact {
-                                    match destructor_config.remove_fn {
-                                        Some(function) => {
-                                            function(&mut commands, destructor_entity, &mut pool)
-                                        }
-                                        None => commands.entity(destructor_entity).despawn(),
-                                    }
-                                }
-
-                                //break;
+                            if storage.get(&pos).is_none() {
+                                continue;
                             }
+
+                            // Один снаряд бьёт тайл только один раз
+                            if !hit_tiles.insert((tilemap, pos)) {
+                                continue;
+                            }
+
+                            let destroyed = health_storage
+                                .as_mut()
+                                .map_or(TileHitOutcome::Destroyed, |health| {
+                                    health.hit(&pos, damage, destructor_config.power)
+                                })
+                                == TileHitOutcome::Destroyed;
+
+                            tile_hits.write(TileHit {
+                                position: point.point,
+                                tilemap,
+                                tile_pos: pos,
+                                destroyed,
+                            });
+
+                            if !destroyed {
+                                deflect_normal.get_or_insert(normal);
+                                continue;
+                            }
+                            destroyed_any = true;
+
+                            tile_remover.remove(
+                                tilemap,
+                                &mut storage,
+                                pos,
+                                map_reference,
+                                affected_by_destructor.layer_id,
+                                is_updated,
+                            );
+
+                            commands.entity(collider_entity).queue_silenced(
+                                |entity_commands: EntityWorldMut<'_>| {
+                                    entity_commands.despawn();
+                                },
+                            );
                         }
                     }
                 }
             }
         }
+
+        // Уцелевший тайл отражает снаряд, если у него остались рикошеты
+        if !destroyed_any
+            && destructor_config.ricochets > 0
+            && let Some(normal) = deflect_normal.and_then(Vec2::try_normalize)
+            && let Some(mut velocity) = velocity
+        {
+            let heading = destructor_config.vector.reflect(normal);
+            let speed = bullet_data.map_or(velocity.0.length(), |bullet| bullet.speed);
+
+            velocity.0 = heading * speed;
+            destructor_config.vector = heading;
+            destructor_config.ricochets -= 1;
+            continue;
+        }
+
+        if destructor_config.remove_on_contact && !hit_tiles.is_empty() {
+            match destructor_config.remove_fn {
+                Some(function) => function(&mut commands, destructor_entity, &mut pool),
+                None => commands.entity(destructor_entity).despawn(),
+            }
+        }
+    }
+}
+
+fn apply_tile_blasts(
+    mut commands: Commands,
+    mut tile_blasts: MessageReader<TileBlast>,
+    mut q_tiled_tilemap: DestructibleLayers,
+    q_colliders: Query<(Entity, &ChildOf), With<Collider>>,
+    mut tile_remover: TileRemover,
+    mut tile_hits: MessageWriter<TileHit>,
+) {
+    let mut updated_layers: HashSet<Entity> = HashSet::new();
+
+    for tile_blast in tile_blasts.read() {
+        for (
+            tilemap,
+            parent_of_layer,
+            &size,
+            &grid,
+            &tile,
+            &map_type,
+            mut storage,
+            &transform,
+            &anchor,
+            map_reference,
+            affected_by_destructor,
+            is_updated,
+            mut health_storage,
+        ) in q_tiled_tilemap.iter_mut()
+        {
+            let Some(center) = get_tile_pos_from_world_pos(
+                transform,
+                tile_blast.position,
+                size,
+                grid,
+                tile,
+                map_type,
+                anchor,
+            ) else {
+                continue;
+            };
+
+            let radius = tile_blast.blast.radius as i32;
+            let center = IVec2::new(center.x as i32, center.y as i32);
+
+            for y in -radius..=radius {
+                for x in -radius..=radius {
+                    let offset = IVec2::new(x, y);
+                    let cell = center + offset;
+                    if !tile_blast.blast.covers(offset)
+                        || cell.x < 0
+                        || cell.y < 0
+                        || cell.x as u32 >= size.x
+                        || cell.y as u32 >= size.y
+                    {
+                        continue;
+                    }
+
+                    let pos = TilePos::new(cell.x as u32, cell.y as u32);
+                    if storage.get(&pos).is_none() {
+                        continue;
+                    }
+
+                    let outcome = health_storage
+                        .as_mut()
+                        .map_or(TileHitOutcome::Destroyed, |health| {
+                            health.hit(&pos, tile_blast.damage, tile_blast.power)
+                        });
+                    if outcome == TileHitOutcome::Blocked {
+                        continue;
+                    }
+
+                    // Искры, обломки и звук — как от попадания пули в этот тайл
+                    let local = pos.center_in_world(&size, &grid, &tile, &map_type, &anchor);
+                    tile_hits.write(TileHit {
+                        position: transform.transform_point(local.extend(0.0)).truncate(),
+                        tilemap,
+                        tile_pos: pos,
+                        destroyed: outcome == TileHitOutcome::Destroyed,
+                    });
+
+                    if outcome != TileHitOutcome::Destroyed {
+                        continue;
+                    }
+
+                    tile_remover.remove(
+                        tilemap,
+                        &mut storage,
+                        pos,
+                        map_reference,
+                        affected_by_destructor.layer_id,
+                        is_updated,
+                    );
+                    updated_layers.insert(parent_of_layer.parent());
+                }
+            }
+        }
+    }
+
+    // Коллайдеры слоя пересобираются один раз, сколько бы тайлов ни снёс взрыв
+    for (collider_entity, parent_of_collider) in q_colliders.iter() {
+        if !updated_layers.contains(&parent_of_collider.parent()) {
+            continue;
+        }
+
+        commands
+            .entity(collider_entity)
+            .queue_silenced(|entity_commands: EntityWorldMut<'_>| {
+                entity_commands.despawn();
+            });
     }
 }
 
-fn get_tile_pos_from_world_pos(
+pub(crate) fn get_tile_pos_from_world_pos(
     transform: GlobalTransform,
     world_point: Vec2,
     size: TilemapSize,
@@ -304,3 +672,95 @@ impl Iterator for PenetrationPoints {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circle_blast_cuts_the_corners() {
+        let blast = Blast {
+            radius: 2,
+            shape: BlastShape::Circle,
+        };
+
+        assert!(blast.covers(IVec2::ZERO));
+        assert!(blast.covers(IVec2::new(2, 0)));
+        assert!(blast.covers(IVec2::new(-1, 1)));
+        assert!(!blast.covers(IVec2::new(2, 1)));
+        assert!(!blast.covers(IVec2::new(-2, -2)));
+        assert!(!blast.covers(IVec2::new(0, 3)));
+    }
+
+    #[test]
+    fn square_blast_covers_the_corners() {
+        let blast = Blast {
+            radius: 1,
+            shape: BlastShape::Square,
+        };
+
+        assert!(blast.covers(IVec2::new(1, 1)));
+        assert!(blast.covers(IVec2::new(-1, 0)));
+        assert!(!blast.covers(IVec2::new(2, 0)));
+        assert!(!blast.covers(IVec2::new(1, -2)));
+    }
+
+    #[test]
+    fn zero_radius_hits_only_the_impact_tile() {
+        for shape in [BlastShape::Circle, BlastShape::Square] {
+            let blast = Blast { radius: 0, shape };
+
+            assert!(blast.covers(IVec2::ZERO));
+            assert!(!blast.covers(IVec2::X));
+            assert!(!blast.covers(IVec2::NEG_Y));
+        }
+    }
+
+    #[test]
+    fn plain_tile_loses_health_until_destroyed() {
+        let pos = TilePos::new(1, 2);
+        let mut storage = TileHealthStorage::default();
+        storage.set(pos, 2.0);
+
+        assert_eq!(storage.hit(&pos, 1.0, 0), TileHitOutcome::Damaged);
+        assert_eq!(storage.get(&pos), Some(1.0));
+        assert_eq!(storage.hit(&pos, 1.0, 5), TileHitOutcome::Destroyed);
+        assert_eq!(storage.get(&pos), None);
+    }
+
+    #[test]
+    fn tile_without_entry_breaks_at_once() {
+        let mut storage = TileHealthStorage::default();
+
+        assert_eq!(
+            storage.hit(&TilePos::new(0, 0), 0.25, 0),
+            TileHitOutcome::Destroyed
+        );
+    }
+
+    #[test]
+    fn armor_blocks_weaker_projectiles() {
+        let pos = TilePos::new(3, 3);
+        let mut storage = TileHealthStorage::default();
+        storage.set(pos, 1.0);
+        storage.set_armor(pos, 2);
+
+        for power in [0, 1] {
+            assert_eq!(storage.hit(&pos, 100.0, power), TileHitOutcome::Blocked);
+        }
+        assert_eq!(storage.get(&pos), Some(1.0));
+        assert_eq!(storage.armor(&pos), 2);
+    }
+
+    #[test]
+    fn enough_power_breaks_armor_regardless_of_health() {
+        let pos = TilePos::new(4, 0);
+        let mut storage = TileHealthStorage::default();
+        storage.set(pos, 1000.0);
+        storage.set_armor(pos, 1);
+
+        assert_eq!(storage.hit(&pos, 0.0, 1), TileHitOutcome::Destroyed);
+        assert_eq!(storage.get(&pos), None);
+        assert_eq!(storage.armor(&pos), 0);
+    }
+}
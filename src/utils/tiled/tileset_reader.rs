@@ -1,17 +1,40 @@
 use bevy::prelude::*;
-use bevy_ecs_tiled::prelude::tiled::{Error, Result, Tileset};
+use bevy_ecs_tiled::prelude::tiled::{Error, Properties, PropertyValue, Result, Tileset};
 use bevy_ecs_tiled::prelude::*;
 use bevy_spritesheet_animation::prelude::{Animation, Spritesheet, SpritesheetAnimation};
 use std::sync::Arc;
+use std::time::Duration;
 
 pub fn read_sprite_animation_from_tileset(
     tileset_name: String, // tank
     tile_id: u32,         // 0
-    tiled_map_assets: Res<Assets<TiledMapAsset>>,
-    mut animations: ResMut<Assets<Animation>>,
-    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    images: ResMut<Assets<Image>>,
+    tiled_map_assets: &Assets<TiledMapAsset>,
+    animations: &mut Assets<Animation>,
+    atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    images: &Assets<Image>,
 ) -> Option<(Sprite, SpritesheetAnimation)> {
+    let (sprite, animation_handle, _) = read_animation_handle_from_tileset(
+        tileset_name,
+        tile_id,
+        tiled_map_assets,
+        animations,
+        atlas_layouts,
+        images,
+    )?;
+
+    Some((sprite, SpritesheetAnimation::new(animation_handle)))
+}
+
+/// Same as [`read_sprite_animation_from_tileset`], but keeps the animation handle
+/// so it can be shared, and returns the total length of one animation cycle.
+pub fn read_animation_handle_from_tileset(
+    tileset_name: String,
+    tile_id: u32,
+    tiled_map_assets: &Assets<TiledMapAsset>,
+    animations: &mut Assets<Animation>,
+    atlas_layouts: &mut Assets<TextureAtlasLayout>,
+    images: &Assets<Image>,
+) -> Option<(Sprite, Handle<Animation>, Duration)> {
     if let Some((tileset, handle, rows)) = read_tileset_data(tileset_name, tiled_map_assets) {
         let tile = tileset.get_tile(tile_id)?;
 
@@ -21,9 +44,9 @@ pub fn read_sprite_animation_from_tileset(
 
         let spritesheet = Spritesheet::new(&handle, tileset.columns as usize, rows as usize);
         let sprite = spritesheet
-            .with_loaded_image(&images)
+            .with_loaded_image(images)
             .expect("")
-            .sprite(&mut atlas_layouts);
+            .sprite(atlas_layouts);
         let mut animation_builder = spritesheet.create_animation();
 
         for frame in animation {
@@ -39,8 +62,12 @@ pub fn read_sprite_animation_from_tileset(
         let final_animation = animation_builder.build();
 
         let animation_handle = animations.add(final_animation);
+        let cycle = animation
+            .iter()
+            .map(|frame| Duration::from_millis(frame.duration as u64))
+            .sum();
 
-        Some((sprite, SpritesheetAnimation::new(animation_handle)))
+        Some((sprite, animation_handle, cycle))
     } else {
         None
     }
@@ -63,8 +90,8 @@ fn calculate_rows(
 pub fn read_sprite_from_tileset(
     tileset_name: String, // tank
     tile_id: u32,         // 0
-    tiled_map_assets: Res<Assets<TiledMapAsset>>,
-    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    tiled_map_assets: &Assets<TiledMapAsset>,
+    atlas_layouts: &mut Assets<TextureAtlasLayout>,
 ) -> Option<Sprite> {
     if let Some((tileset, handle, rows)) = read_tileset_data(tileset_name, tiled_map_assets) {
         // Рассчитываем количество строк в спрайтшите
@@ -96,7 +123,7 @@ pub fn read_sprite_from_tileset(
 
 fn read_tileset_data(
     tileset_name: String,
-    tiled_map_assets: Res<Assets<TiledMapAsset>>,
+    tiled_map_assets: &Assets<TiledMapAsset>,
 ) -> Option<(Arc<Tileset>, Handle<Image>, u32)> {
     let mut tileset_option: Option<&Arc<Tileset>> = None;
     let mut handle_option: Option<&Handle<Image>> = None;
@@ -117,7 +144,7 @@ fn read_tileset_data(
         }
 
         for (name, tileset) in asset.tilesets.iter() {
-            if name.contains(tileset_name.as_str()) {
+            if tileset_key_matches(name, &tileset_name) {
                 let handlers = tileset.tilemap_texture.image_handles();
                 handle_option = Some(*handlers.first().unwrap());
                 break;
@@ -149,3 +176,49 @@ fn read_tileset_data(
 
     Some((tileset.clone(), handle_option.unwrap().clone(), rows))
 }
+
+/// Tileset keys contain the tileset name, make sure `boom` doesn't match `boom_big`.
+fn tileset_key_matches(key: &str, tileset_name: &str) -> bool {
+    key.match_indices(tileset_name).any(|(index, _)| {
+        key[index + tileset_name.len()..]
+            .chars()
+            .next()
+            .is_none_or(|next| !next.is_alphanumeric() && next != '_')
+    })
+}
+
+pub fn find_tileset(
+    tileset_name: &str,
+    tiled_map_assets: &Assets<TiledMapAsset>,
+) -> Option<Arc<Tileset>> {
+    tiled_map_assets.iter().find_map(|(_, asset)| {
+        asset
+            .map
+            .tilesets()
+            .iter()
+            .find(|ts| ts.name == tileset_name)
+            .cloned()
+    })
+}
+
+pub fn read_f32_property(properties: &Properties, name: &str) -> Option<f32> {
+    match properties.get(name)? {
+        PropertyValue::IntValue(value) => Some(*value as f32),
+        PropertyValue::FloatValue(value) => Some(*value),
+        _ => None,
+    }
+}
+
+pub fn read_bool_property(properties: &Properties, name: &str) -> Option<bool> {
+    match properties.get(name)? {
+        PropertyValue::BoolValue(value) => Some(*value),
+        _ => None,
+    }
+}
+
+pub fn read_string_property<'a>(properties: &'a Properties, name: &str) -> Option<&'a str> {
+    match properties.get(name)? {
+        PropertyValue::StringValue(value) => Some(value.as_str()),
+        _ => None,
+    }
+}